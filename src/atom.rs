@@ -0,0 +1,78 @@
+use core::fmt;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+thread_local! {
+    static KEYWORDS: RefCell<HashSet<Rc<str>>> = RefCell::new(HashSet::new());
+}
+
+/// A keyword such as `:name`. Keywords are interned, so two keywords with
+/// the same name share one allocation and compare by pointer.
+#[derive(Clone)]
+pub struct Keyword(Rc<str>);
+
+impl Keyword {
+    /// Interns `name`, which does not include the leading `:`.
+    pub fn new(name: &str) -> Keyword {
+        KEYWORDS.with(|keywords| {
+            let mut keywords = keywords.borrow_mut();
+            if let Some(interned) = keywords.get(name) {
+                return Keyword(interned.clone());
+            }
+            let interned: Rc<str> = Rc::from(name);
+            keywords.insert(interned.clone());
+            Keyword(interned)
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Keyword {
+    fn eq(&self, other: &Keyword) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Keyword {}
+
+impl Hash for Keyword {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.0).hash(state);
+    }
+}
+
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, ":{}", self.0)
+    }
+}
+
+/// Characters that are written by name rather than as themselves after `#\`.
+const CHAR_NAMES: [(&str, char); 5] = [
+    ("newline", '\n'),
+    ("space", ' '),
+    ("tab", '\t'),
+    ("return", '\r'),
+    ("nul", '\0'),
+];
+
+pub fn char_from_name(name: &str) -> Option<char> {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => CHAR_NAMES.iter().find(|(n, _)| *n == name).map(|&(_, c)| c),
+    }
+}
+
+/// How `c` is written as a character literal, without the `#\`.
+pub fn char_name(c: char) -> String {
+    match CHAR_NAMES.iter().find(|&&(_, named)| named == c) {
+        Some((name, _)) => name.to_string(),
+        None => c.to_string(),
+    }
+}
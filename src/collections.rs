@@ -0,0 +1,304 @@
+use crate::list::RispList;
+use crate::number::Number;
+use crate::risp_type::*;
+use im_rc::{HashMap as RispMap, HashSet as RispSet, Vector as RispVector};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+fn parse_map(exp: &RispExp, name: &str) -> Result<RispMap<RispExp, RispExp>, RispErr> {
+    match exp {
+        RispExp::Map(map, _) => Ok(map.clone()),
+        RispExp::Nil => Ok(RispMap::new()),
+        _ => Err(RispErr::Type(format!(
+            "{name}: expected a map, got '{exp}'"
+        ))),
+    }
+}
+
+fn parse_set(exp: &RispExp, name: &str) -> Result<RispSet<RispExp>, RispErr> {
+    match exp {
+        RispExp::Set(set, _) => Ok(set.clone()),
+        RispExp::Nil => Ok(RispSet::new()),
+        _ => Err(RispErr::Type(format!(
+            "{name}: expected a set, got '{exp}'"
+        ))),
+    }
+}
+
+fn parse_vector(exp: &RispExp, name: &str) -> Result<RispVector<RispExp>, RispErr> {
+    match exp {
+        RispExp::Vector(v) => Ok(v.clone()),
+        _ => Err(RispErr::Type(format!(
+            "{name}: expected a vector, got '{exp}'"
+        ))),
+    }
+}
+
+/// Builds a map from a flat `k1 v1 k2 v2 ...` argument list.
+fn insert_pairs(
+    mut map: RispMap<RispExp, RispExp>,
+    pairs: &[RispExp],
+    name: &str,
+) -> Result<RispMap<RispExp, RispExp>, RispErr> {
+    if !pairs.len().is_multiple_of(2) {
+        return Err(RispErr::Type(format!(
+            "{name}: expected an even number of key and value arguments"
+        )));
+    }
+    for pair in pairs.chunks(2) {
+        map.insert(pair[0].clone(), pair[1].clone());
+    }
+    Ok(map)
+}
+
+fn cons_all(list: RispList, items: &[RispExp]) -> RispList {
+    items.iter().fold(list, |list, x| list.cons(x.clone()))
+}
+
+/// `(assoc v index value ...)` replaces elements of a vector. An index one
+/// past the end appends.
+fn assoc_vector(mut v: RispVector<RispExp>, pairs: &[RispExp]) -> RispResult {
+    if !pairs.len().is_multiple_of(2) {
+        return Err(RispErr::Reason(
+            "assoc: expected an even number of index and value arguments".to_string(),
+        ));
+    }
+    for pair in pairs.chunks(2) {
+        let idx = parse_index(&pair[0], "assoc")?;
+        match idx.cmp(&v.len()) {
+            Ordering::Less => {
+                v.set(idx, pair[1].clone());
+            }
+            Ordering::Equal => v.push_back(pair[1].clone()),
+            Ordering::Greater => {
+                return Err(RispErr::Reason(format!(
+                    "assoc: index {idx} out of bounds for a vector of length {}",
+                    v.len()
+                )))
+            }
+        }
+    }
+    Ok(RispExp::Vector(v))
+}
+
+/// Adds the map, set and vector builtins to a standard environment.
+pub fn insert_builtins(data: &mut HashMap<String, RispExp>) {
+    insert_func(
+        data,
+        RispFunc::new("hash-map", Arity::AtLeast(0), |args, _| {
+            let map = insert_pairs(RispMap::new(), args, "hash-map")?;
+            Ok(RispExp::Map(map, None))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("hash-set", Arity::AtLeast(0), |args, _| {
+            Ok(RispExp::Set(args.iter().cloned().collect(), None))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("get", Arity::Between(2, 3), |args, _| {
+            let (coll, key, default) = match args {
+                [coll, key] => (coll, key, RispExp::Nil),
+                [coll, key, default] => (coll, key, default.clone()),
+                _ => unreachable!("arity is checked by call"),
+            };
+            let found = match coll {
+                RispExp::Map(map, _) => map.get(key).cloned(),
+                RispExp::Set(set, _) => set.contains(key).then(|| key.clone()),
+                RispExp::Vector(v) => match key {
+                    RispExp::Number(n) => n.to_index().and_then(|i| v.get(i)).cloned(),
+                    _ => None,
+                },
+                RispExp::Nil => None,
+                _ => {
+                    return Err(RispErr::Type(format!(
+                        "get: expected a map, set or vector, got '{coll}'"
+                    )))
+                }
+            };
+            Ok(found.unwrap_or(default))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("assoc", Arity::AtLeast(3), |args, _| {
+            let (map, pairs) = args.split_first().expect("arity is checked by call");
+            if let RispExp::Vector(v) = map {
+                return assoc_vector(v.clone(), pairs);
+            }
+            let map = insert_pairs(parse_map(map, "assoc")?, pairs, "assoc")?;
+            Ok(RispExp::Map(map, None))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("dissoc", Arity::AtLeast(1), |args, _| {
+            let (map, keys) = args.split_first().expect("arity is checked by call");
+            let mut map = parse_map(map, "dissoc")?;
+            for key in keys {
+                map.remove(key);
+            }
+            Ok(RispExp::Map(map, None))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("keys", |[map], _| {
+            let keys = parse_map(map, "keys")?.keys().cloned().collect();
+            Ok(RispExp::List(keys, None))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("vals", |[map], _| {
+            let vals = parse_map(map, "vals")?.values().cloned().collect();
+            Ok(RispExp::List(vals, None))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("contains?", |[coll, key], _| match coll {
+            RispExp::Map(map, _) => Ok(RispExp::Bool(map.contains_key(key))),
+            RispExp::Set(set, _) => Ok(RispExp::Bool(set.contains(key))),
+            RispExp::Nil => Ok(RispExp::Bool(false)),
+            _ => Err(RispErr::Type(format!(
+                "contains?: expected a map or set, got '{coll}'"
+            ))),
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("merge", Arity::AtLeast(0), |args, _| {
+            if args.is_empty() {
+                return Ok(RispExp::Nil);
+            }
+            let mut merged = RispMap::new();
+            for map in args {
+                // Later maps win, so they are the left operand of the union.
+                merged = parse_map(map, "merge")?.union(merged);
+            }
+            Ok(RispExp::Map(merged, None))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("conj", Arity::AtLeast(1), |args, _| {
+            let (coll, items) = args.split_first().expect("arity is checked by call");
+            match coll {
+                RispExp::Set(set, _) => {
+                    let mut set = set.clone();
+                    set.extend(items.iter().cloned());
+                    Ok(RispExp::Set(set, None))
+                }
+                RispExp::Map(map, _) => {
+                    let mut map = map.clone();
+                    for item in items {
+                        match item {
+                            RispExp::List(pair, _) if pair.len() == 2 => {
+                                let pair = pair.to_vec();
+                                map.insert(pair[0].clone(), pair[1].clone());
+                            }
+                            RispExp::Vector(pair) if pair.len() == 2 => {
+                                map.insert(pair[0].clone(), pair[1].clone());
+                            }
+                            RispExp::Map(other, _) => map = other.clone().union(map),
+                            _ => {
+                                return Err(RispErr::Type(format!(
+                                    "conj: expected a (key value) pair or map, got '{item}'"
+                                )))
+                            }
+                        }
+                    }
+                    Ok(RispExp::Map(map, None))
+                }
+                // Lists grow at the front and vectors at the back.
+                RispExp::List(list, _) => Ok(RispExp::List(cons_all(list.clone(), items), None)),
+                RispExp::Nil => Ok(RispExp::List(cons_all(RispList::new(), items), None)),
+                RispExp::Vector(v) => {
+                    let mut v = v.clone();
+                    v.extend(items.iter().cloned());
+                    Ok(RispExp::Vector(v))
+                }
+                _ => Err(RispErr::Type(format!(
+                    "conj: expected a collection, got '{coll}'"
+                ))),
+            }
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("disj", Arity::AtLeast(1), |args, _| {
+            let (set, items) = args.split_first().expect("arity is checked by call");
+            let mut set = parse_set(set, "disj")?;
+            for item in items {
+                set.remove(item);
+            }
+            Ok(RispExp::Set(set, None))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("vector", Arity::AtLeast(0), |args, _| {
+            Ok(RispExp::Vector(args.iter().cloned().collect()))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("vec", |[coll], _| match coll {
+            RispExp::Vector(_) => Ok(coll.clone()),
+            RispExp::List(list, _) => Ok(RispExp::Vector(list.iter().cloned().collect())),
+            RispExp::Set(set, _) => Ok(RispExp::Vector(set.iter().cloned().collect())),
+            RispExp::Nil => Ok(RispExp::Vector(RispVector::new())),
+            _ => Err(RispErr::Type(format!(
+                "vec: expected a collection, got '{coll}'"
+            ))),
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("count", |[coll], _| {
+            let count = match coll {
+                RispExp::List(list, _) => list.len(),
+                RispExp::Vector(v) => v.len(),
+                RispExp::Map(map, _) => map.len(),
+                RispExp::Set(set, _) => set.len(),
+                RispExp::Literal(s) => s.chars().count(),
+                RispExp::Nil => 0,
+                _ => {
+                    return Err(RispErr::Type(format!(
+                        "count: expected a collection, got '{coll}'"
+                    )))
+                }
+            };
+            Ok(RispExp::Number(Number::Int(count as i64)))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("subvec", Arity::Between(2, 3), |args, _| {
+            let (v, start, end) = match args {
+                [v, start] => {
+                    let v = parse_vector(v, "subvec")?;
+                    let len = v.len();
+                    (v, parse_index(start, "subvec")?, len)
+                }
+                [v, start, end] => (
+                    parse_vector(v, "subvec")?,
+                    parse_index(start, "subvec")?,
+                    parse_index(end, "subvec")?,
+                ),
+                _ => unreachable!("arity is checked by call"),
+            };
+            if start > end || end > v.len() {
+                return Err(RispErr::Reason(format!(
+                    "subvec: range {start}..{end} out of bounds for a vector of length {}",
+                    v.len()
+                )));
+            }
+            let mut v = v;
+            Ok(RispExp::Vector(v.slice(start..end)))
+        }),
+    );
+}
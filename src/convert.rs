@@ -0,0 +1,210 @@
+use crate::number::Number;
+use crate::risp_type::*;
+use num_traits::ToPrimitive;
+
+/// Rust types that can be taken from a Risp value, used for the arguments
+/// of functions registered with `Interpreter::register_fn`.
+pub trait FromRisp: Sized {
+    fn from_risp(exp: &RispExp) -> Result<Self, RispErr>;
+}
+
+/// Rust types that can be turned into a Risp value.
+pub trait IntoRisp {
+    fn into_risp(self) -> RispExp;
+}
+
+/// What a registered function may return: a value, or a `Result` whose
+/// error is raised in Risp.
+pub trait IntoRispResult {
+    fn into_risp_result(self) -> RispResult;
+}
+
+fn mismatch(expected: &str, exp: &RispExp) -> RispErr {
+    RispErr::Type(format!("expected {expected}, got '{exp}'"))
+}
+
+impl FromRisp for RispExp {
+    fn from_risp(exp: &RispExp) -> Result<RispExp, RispErr> {
+        Ok(exp.clone())
+    }
+}
+
+impl FromRisp for Number {
+    fn from_risp(exp: &RispExp) -> Result<Number, RispErr> {
+        match exp {
+            RispExp::Number(n) => Ok(n.clone()),
+            _ => Err(mismatch("a number", exp)),
+        }
+    }
+}
+
+/// Accepts any number, converting exact ones.
+impl FromRisp for f64 {
+    fn from_risp(exp: &RispExp) -> Result<f64, RispErr> {
+        Ok(Number::from_risp(exp)?.to_f64())
+    }
+}
+
+/// Accepts exact integers in the range of an `i64`, including big integers
+/// that were not normalized.
+impl FromRisp for i64 {
+    fn from_risp(exp: &RispExp) -> Result<i64, RispErr> {
+        match exp {
+            RispExp::Number(Number::Int(i)) => Ok(*i),
+            RispExp::Number(Number::Big(n)) => n
+                .to_i64()
+                .ok_or_else(|| mismatch("an integer that fits in 64 bits", exp)),
+            _ => Err(mismatch("an integer", exp)),
+        }
+    }
+}
+
+impl FromRisp for bool {
+    fn from_risp(exp: &RispExp) -> Result<bool, RispErr> {
+        match exp {
+            RispExp::Bool(b) => Ok(*b),
+            _ => Err(mismatch("a boolean", exp)),
+        }
+    }
+}
+
+impl FromRisp for String {
+    fn from_risp(exp: &RispExp) -> Result<String, RispErr> {
+        match exp {
+            RispExp::Literal(s) => Ok(s.clone()),
+            _ => Err(mismatch("a string", exp)),
+        }
+    }
+}
+
+/// `nil` is `None`.
+impl<T: FromRisp> FromRisp for Option<T> {
+    fn from_risp(exp: &RispExp) -> Result<Option<T>, RispErr> {
+        match exp {
+            RispExp::Nil => Ok(None),
+            _ => T::from_risp(exp).map(Some),
+        }
+    }
+}
+
+/// Accepts lists and vectors.
+impl<T: FromRisp> FromRisp for Vec<T> {
+    fn from_risp(exp: &RispExp) -> Result<Vec<T>, RispErr> {
+        match exp {
+            RispExp::List(list, _) => list.iter().map(T::from_risp).collect(),
+            RispExp::Vector(v) => v.iter().map(T::from_risp).collect(),
+            RispExp::Nil => Ok(vec![]),
+            _ => Err(mismatch("a list", exp)),
+        }
+    }
+}
+
+impl IntoRisp for RispExp {
+    fn into_risp(self) -> RispExp {
+        self
+    }
+}
+
+impl IntoRisp for Number {
+    fn into_risp(self) -> RispExp {
+        RispExp::Number(self)
+    }
+}
+
+impl IntoRisp for f64 {
+    fn into_risp(self) -> RispExp {
+        RispExp::Number(Number::Float(self))
+    }
+}
+
+impl IntoRisp for i64 {
+    fn into_risp(self) -> RispExp {
+        RispExp::Number(Number::Int(self))
+    }
+}
+
+impl IntoRisp for bool {
+    fn into_risp(self) -> RispExp {
+        RispExp::Bool(self)
+    }
+}
+
+impl IntoRisp for String {
+    fn into_risp(self) -> RispExp {
+        RispExp::Literal(self)
+    }
+}
+
+impl IntoRisp for &str {
+    fn into_risp(self) -> RispExp {
+        RispExp::Literal(self.to_string())
+    }
+}
+
+impl IntoRisp for () {
+    fn into_risp(self) -> RispExp {
+        RispExp::Nil
+    }
+}
+
+impl<T: IntoRisp> IntoRisp for Option<T> {
+    fn into_risp(self) -> RispExp {
+        match self {
+            Some(x) => x.into_risp(),
+            None => RispExp::Nil,
+        }
+    }
+}
+
+/// Becomes a list.
+impl<T: IntoRisp> IntoRisp for Vec<T> {
+    fn into_risp(self) -> RispExp {
+        RispExp::List(self.into_iter().map(T::into_risp).collect(), None)
+    }
+}
+
+impl<T: IntoRisp> IntoRispResult for T {
+    fn into_risp_result(self) -> RispResult {
+        Ok(self.into_risp())
+    }
+}
+
+impl<T: IntoRisp> IntoRispResult for Result<T, RispErr> {
+    fn into_risp_result(self) -> RispResult {
+        self.map(T::into_risp)
+    }
+}
+
+/// Rust closures that can be registered as Risp functions. `Args` is the
+/// tuple of the closure's argument types, which only serves to tell the
+/// implementations for different arities apart.
+pub trait NativeFunction<Args> {
+    fn into_func(self, name: &str) -> RispFunc;
+}
+
+macro_rules! impl_native_function {
+    ($arity:expr; $($arg:ident),*) => {
+        impl<F, R, $($arg),*> NativeFunction<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + 'static,
+            R: IntoRispResult,
+            $($arg: FromRisp,)*
+        {
+            #[allow(unused_variables, unused_mut)]
+            fn into_func(self, name: &str) -> RispFunc {
+                RispFunc::new(name, Arity::Exact($arity), move |args, _| {
+                    let mut args = args.iter();
+                    self($($arg::from_risp(args.next().expect("arity is checked by call"))?),*)
+                        .into_risp_result()
+                })
+            }
+        }
+    };
+}
+
+impl_native_function!(0;);
+impl_native_function!(1; A);
+impl_native_function!(2; A, B);
+impl_native_function!(3; A, B, C);
+impl_native_function!(4; A, B, C, D);
+impl_native_function!(5; A, B, C, D, E);
@@ -0,0 +1,267 @@
+//! Converting Risp values to Rust with serde, the reverse of `to_value`.
+//! Maps fill structs whether their keys are keywords, strings or symbols,
+//! and lists, vectors and sets all fill sequences. Errors name the path to
+//! the offending value, e.g. "at `servers[1].port`: invalid type: string
+//! "80", expected u16".
+
+use crate::number::Number;
+use crate::risp_type::{RispErr, RispExp};
+use num_traits::ToPrimitive;
+use serde::de::{self, Deserialize, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use std::fmt;
+
+impl de::Error for RispErr {
+    fn custom<T: fmt::Display>(msg: T) -> RispErr {
+        RispErr::Reason(msg.to_string())
+    }
+}
+
+/// Converts a Risp value to `T`, e.g. to read a configuration map returned
+/// by `Interpreter::eval_str` into a Rust struct.
+pub fn from_value<'de, T: Deserialize<'de>>(value: &'de RispExp) -> Result<T, RispErr> {
+    T::deserialize(Deserializer::new(value))
+}
+
+/// The deserializer behind `from_value`, reading a borrowed value.
+pub struct Deserializer<'de> {
+    exp: &'de RispExp,
+}
+
+impl<'de> Deserializer<'de> {
+    pub fn new(exp: &'de RispExp) -> Deserializer<'de> {
+        Deserializer { exp }
+    }
+
+    /// The text of keywords, symbols and strings, which can all stand for
+    /// field and variant names.
+    fn name(&self) -> Option<&'de str> {
+        match self.exp {
+            RispExp::Keyword(k) => Some(k.name()),
+            RispExp::Symbol(s, _) => Some(s),
+            RispExp::Literal(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// How a value is described in type errors.
+fn unexpected(exp: &RispExp) -> de::Unexpected<'_> {
+    match exp {
+        RispExp::Nil => de::Unexpected::Unit,
+        RispExp::Bool(b) => de::Unexpected::Bool(*b),
+        RispExp::Number(Number::Int(i)) => de::Unexpected::Signed(*i),
+        RispExp::Number(Number::Float(f)) => de::Unexpected::Float(*f),
+        RispExp::Number(_) => de::Unexpected::Other("number"),
+        RispExp::Char(c) => de::Unexpected::Char(*c),
+        RispExp::Literal(s) => de::Unexpected::Str(s),
+        RispExp::Keyword(_) => de::Unexpected::Other("keyword"),
+        RispExp::Symbol(..) => de::Unexpected::Other("symbol"),
+        RispExp::List(..) | RispExp::Vector(_) | RispExp::Set(..) => de::Unexpected::Seq,
+        RispExp::Map(..) => de::Unexpected::Map,
+        RispExp::Func(_) | RispExp::Lambda(_) | RispExp::Macro(_) => {
+            de::Unexpected::Other("function")
+        }
+        RispExp::Error(_) => de::Unexpected::Other("error"),
+        RispExp::Pattern(_) => de::Unexpected::Other("regex"),
+        RispExp::Port(_) => de::Unexpected::Other("port"),
+    }
+}
+
+/// How a map key is shown in the path of an error.
+fn key_path(key: &RispExp) -> String {
+    match key {
+        RispExp::Keyword(k) => k.name().to_string(),
+        RispExp::Literal(s) => s.clone(),
+        _ => format!("[{key}]"),
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+    type Error = RispErr;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RispErr> {
+        match self.exp {
+            RispExp::Nil => visitor.visit_unit(),
+            RispExp::Bool(b) => visitor.visit_bool(*b),
+            RispExp::Number(Number::Int(i)) => visitor.visit_i64(*i),
+            // Try the narrowest type first, since serde's visitors for u64
+            // do not accept an i128.
+            RispExp::Number(Number::Big(n)) => {
+                if let Some(u) = n.to_u64() {
+                    visitor.visit_u64(u)
+                } else if let Some(i) = n.to_i128() {
+                    visitor.visit_i128(i)
+                } else if let Some(u) = n.to_u128() {
+                    visitor.visit_u128(u)
+                } else {
+                    Err(de::Error::custom(format!("integer {n} is out of range")))
+                }
+            }
+            RispExp::Number(n) => visitor.visit_f64(n.to_f64()),
+            RispExp::Char(c) => visitor.visit_char(*c),
+            RispExp::Literal(s) => visitor.visit_borrowed_str(s),
+            RispExp::Keyword(k) => visitor.visit_borrowed_str(k.name()),
+            RispExp::Symbol(s, _) => visitor.visit_borrowed_str(s),
+            RispExp::List(list, _) => visitor.visit_seq(SeqAccess::new(list.iter())),
+            RispExp::Vector(v) => visitor.visit_seq(SeqAccess::new(v.iter())),
+            RispExp::Set(set, _) => visitor.visit_seq(SeqAccess::new(set.iter())),
+            RispExp::Map(map, _) => visitor.visit_map(MapAccess {
+                iter: map.iter(),
+                entry: None,
+            }),
+            RispExp::Func(_)
+            | RispExp::Lambda(_)
+            | RispExp::Macro(_)
+            | RispExp::Error(_)
+            | RispExp::Pattern(_)
+            | RispExp::Port(_) => Err(de::Error::invalid_type(unexpected(self.exp), &visitor)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RispErr> {
+        match self.exp {
+            RispExp::Nil => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, RispErr> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Unit variants are written as keywords (or strings) and the others as
+    /// a one-entry map from the variant's name to its contents.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RispErr> {
+        if let Some(name) = self.name() {
+            return visitor.visit_enum(name.into_deserializer());
+        }
+        match self.exp {
+            RispExp::Map(map, _) if map.len() == 1 => {
+                let (name, value) = map.iter().next().expect("the map has one entry");
+                visitor
+                    .visit_enum(EnumAccess { name, value })
+                    .map_err(|err| err.in_field(&key_path(name)))
+            }
+            _ => Err(de::Error::invalid_type(unexpected(self.exp), &visitor)),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct SeqAccess<I> {
+    iter: I,
+    index: usize,
+}
+
+impl<I> SeqAccess<I> {
+    fn new(iter: I) -> SeqAccess<I> {
+        SeqAccess { iter, index: 0 }
+    }
+}
+
+impl<'de, I: Iterator<Item = &'de RispExp>> de::SeqAccess<'de> for SeqAccess<I> {
+    type Error = RispErr;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, RispErr> {
+        let exp = match self.iter.next() {
+            Some(exp) => exp,
+            None => return Ok(None),
+        };
+        let index = self.index;
+        self.index += 1;
+        seed.deserialize(Deserializer::new(exp))
+            .map(Some)
+            .map_err(|err| err.in_field(&format!("[{index}]")))
+    }
+}
+
+struct MapAccess<'de> {
+    iter: im_rc::hashmap::Iter<'de, RispExp, RispExp>,
+    /// The entry whose key was just read, for `next_value_seed`.
+    entry: Option<(&'de RispExp, &'de RispExp)>,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'de> {
+    type Error = RispErr;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, RispErr> {
+        let (key, value) = match self.iter.next() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        self.entry = Some((key, value));
+        seed.deserialize(Deserializer::new(key)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, RispErr> {
+        let (key, value) = self
+            .entry
+            .take()
+            .expect("next_value is called after next_key");
+        seed.deserialize(Deserializer::new(value))
+            .map_err(|err| err.in_field(&key_path(key)))
+    }
+}
+
+struct EnumAccess<'de> {
+    name: &'de RispExp,
+    value: &'de RispExp,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess<'de> {
+    type Error = RispErr;
+    type Variant = Deserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Deserializer<'de>), RispErr> {
+        let variant = seed.deserialize(Deserializer::new(self.name))?;
+        Ok((variant, Deserializer::new(self.value)))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Deserializer<'de> {
+    type Error = RispErr;
+
+    fn unit_variant(self) -> Result<(), RispErr> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, RispErr> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, RispErr> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RispErr> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
@@ -0,0 +1,68 @@
+use crate::printer::display_string;
+use crate::risp_type::*;
+use std::collections::HashMap;
+
+fn parse_error<'a>(exp: &'a RispExp, name: &str) -> Result<&'a RispError, RispErr> {
+    match exp {
+        RispExp::Error(e) => Ok(e),
+        _ => Err(RispErr::Type(format!(
+            "{name}: expected an error, got '{exp}'"
+        ))),
+    }
+}
+
+/// Adds the builtins for raising and inspecting errors to a standard
+/// environment. Catching them is the `try` special form.
+pub fn insert_builtins(data: &mut HashMap<String, RispExp>) {
+    // Any value can be thrown, not only errors.
+    for name in ["throw", "raise"] {
+        insert_func(
+            data,
+            RispFunc::fixed(name, |[value], _| Err(RispErr::Thrown(value.clone()))),
+        );
+    }
+    insert_func(
+        data,
+        RispFunc::new("error", Arity::Between(1, 2), |args, _| {
+            let (message, data) = match args {
+                [message] => (message, RispExp::Nil),
+                [message, data] => (message, data.clone()),
+                _ => unreachable!("arity is checked by call"),
+            };
+            if !matches!(data, RispExp::Map(..) | RispExp::Nil) {
+                return Err(RispErr::Type(format!(
+                    "error: expected a map of data, got '{data}'"
+                )));
+            }
+            Ok(RispError::value("error", display_string(message), data))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("error?", |[exp], _| {
+            Ok(RispExp::Bool(matches!(exp, RispExp::Error(_))))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("error-message", |[exp], _| {
+            Ok(RispExp::Literal(
+                parse_error(exp, "error-message")?.message.clone(),
+            ))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("error-kind", |[exp], _| {
+            Ok(RispExp::Keyword(
+                parse_error(exp, "error-kind")?.kind.clone(),
+            ))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("error-data", |[exp], _| {
+            Ok(parse_error(exp, "error-data")?.data.clone())
+        }),
+    );
+}
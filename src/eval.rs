@@ -1,182 +1,786 @@
+use crate::lexer::tokenize;
+use crate::list::RispList;
 use crate::parser::*;
-use crate::repl::parse_eval;
+use crate::port::current_output;
+use crate::printer::display_string;
 use crate::risp_type::*;
+use crate::span::*;
 use std::collections::HashMap;
-use std::convert::TryFrom;
 use std::fs;
 use std::rc::Rc;
 
-fn eval_built_in_func(exp: &RispExp, args: &[RispExp], env: &mut RispEnv) -> Option<RispResult> {
+/// The result of evaluating one step of a form. Forms in tail position hand
+/// back the expression to continue with instead of recursing, so `eval` can
+/// run them in a loop without growing the Rust stack.
+enum TailCall {
+    Done(RispExp),
+    Eval(RispExp, RispEnv),
+}
+
+fn eval_tail_form(
+    exp: &RispExp,
+    args: &RispList,
+    env: &RispEnv,
+) -> Option<Result<TailCall, RispErr>> {
     match exp {
-        RispExp::Symbol(s) => match s.as_ref() {
+        RispExp::Symbol(s, _) => match s.as_ref() {
             "if" => Some(eval_if_arg(args, env)),
-            "let" => Some(eval_let_arg(args, env)),
-            "fn" => Some(eval_lambda_arg(args)),
+            "do" | "begin" => Some(eval_do_arg(args, env)),
+            "let" => Some(eval_let_arg(args, env, LetKind::Let)),
+            "let*" => Some(eval_let_arg(args, env, LetKind::LetStar)),
+            "letrec" => Some(eval_let_arg(args, env, LetKind::LetRec)),
+            "when" => Some(eval_when_arg(args, env, true)),
+            "unless" => Some(eval_when_arg(args, env, false)),
+            "cond" => Some(eval_cond_arg(args, env)),
+            "and" => Some(eval_and_or_arg(args, env, false)),
+            "or" => Some(eval_and_or_arg(args, env, true)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn eval_built_in_func(exp: &RispExp, args: &RispList, env: &RispEnv) -> Option<RispResult> {
+    match exp {
+        RispExp::Symbol(s, _) => match s.as_ref() {
+            "quote" => Some(eval_quote_arg(args)),
+            "quasiquote" => Some(eval_quasiquote_arg(args, env)),
+            "unquote" | "unquote-splicing" => Some(Err(RispErr::Reason(format!(
+                "'{s}' used outside of quasiquote"
+            )))),
+            "def" | "define" => Some(eval_def_arg(args, env)),
+            "set!" => Some(eval_set_arg(args, env)),
+            "fn" => Some(eval_lambda_arg(args, env)),
+            "defmacro" => Some(eval_defmacro_arg(args, env)),
+            "macroexpand-1" => Some(eval_macroexpand_arg(args, env, false)),
+            "macroexpand" => Some(eval_macroexpand_arg(args, env, true)),
             "load" => Some(eval_load_risp_file(args, env)),
             "env" => Some(eval_print_env(env)),
-            "print" => Some(eval_print(args, env)),
             "read-file" => Some(eval_read_file(args, env)),
+            "try" => Some(eval_try_arg(args, env)),
             _ => None,
         },
         _ => None,
     }
 }
 
-fn eval_read_file(args: &[RispExp], env: &mut RispEnv) -> RispResult {
+/// The arguments of a special form that takes exactly `N` of them.
+fn fixed_args<'a, const N: usize>(
+    args: &'a RispList,
+    name: &str,
+) -> Result<[&'a RispExp; N], RispErr> {
+    if args.len() != N {
+        return Err(RispErr::Reason(format!(
+            "Wrong number of arguments: {name}, {N}"
+        )));
+    }
+    let mut items = args.iter();
+    Ok([(); N].map(|_| items.next().expect("the length is checked")))
+}
+
+fn eval_quote_arg(args: &RispList) -> RispResult {
+    let [exp] = fixed_args(args, "quote")?;
+    Ok(exp.clone())
+}
+
+fn eval_quasiquote_arg(args: &RispList, env: &RispEnv) -> RispResult {
+    let [exp] = fixed_args(args, "quasiquote")?;
+    quasiquote(exp, env, 1)
+}
+
+/// Returns the argument of `(head x)` if `exp` is such a form.
+fn quoted_form<'a>(exp: &'a RispExp, head: &str) -> Option<&'a RispExp> {
+    match exp {
+        RispExp::List(list, _) => {
+            let mut items = list.iter();
+            match (items.next(), items.next(), items.next()) {
+                (Some(RispExp::Symbol(s, _)), Some(arg), None) if s == head => Some(arg),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Builds the template `exp`, evaluating the forms unquoted at `depth` 1.
+/// Nested quasiquotes raise the depth so their unquotes are left in place.
+fn quasiquote(exp: &RispExp, env: &RispEnv, depth: usize) -> RispResult {
+    let (list, span) = match exp {
+        RispExp::List(list, span) => (list, span),
+        RispExp::Vector(v) => {
+            return Ok(RispExp::Vector(
+                quasiquote_items(v.iter(), env, depth)?.into(),
+            ));
+        }
+        RispExp::Map(map, literal) => {
+            let items = quasiquote_items(map_forms(map, literal).into_iter(), env, depth);
+            return build_map(items?).map_err(|e| e.within(&literal_span(literal)));
+        }
+        RispExp::Set(set, literal) => {
+            let items = quasiquote_items(set_forms(set, literal).into_iter(), env, depth);
+            return build_set(items?).map_err(|e| e.within(&literal_span(literal)));
+        }
+        _ => return Ok(exp.clone()),
+    };
+    if let Some(arg) = quoted_form(exp, "unquote") {
+        return match depth {
+            1 => eval(arg, env),
+            _ => Ok(RispExp::List(
+                vec![head(list), quasiquote(arg, env, depth - 1)?].into(),
+                span.clone(),
+            )),
+        };
+    }
+    if let Some(arg) = quoted_form(exp, "quasiquote") {
+        return Ok(RispExp::List(
+            vec![head(list), quasiquote(arg, env, depth + 1)?].into(),
+            span.clone(),
+        ));
+    }
+    Ok(RispExp::List(
+        quasiquote_items(list.iter(), env, depth)?.into(),
+        span.clone(),
+    ))
+}
+
+fn head(list: &RispList) -> RispExp {
+    list.first().expect("a quoted form has a head").clone()
+}
+
+/// Builds the items of a list or vector template, splicing in the values of
+/// `unquote-splicing` forms.
+fn quasiquote_items<'a>(
+    items: impl Iterator<Item = &'a RispExp>,
+    env: &RispEnv,
+    depth: usize,
+) -> Result<Vec<RispExp>, RispErr> {
+    let mut res = vec![];
+    for item in items {
+        match quoted_form(item, "unquote-splicing") {
+            Some(arg) if depth == 1 => match eval(arg, env)? {
+                RispExp::List(spliced, _) => res.extend(spliced.iter().cloned()),
+                RispExp::Vector(spliced) => res.extend(spliced),
+                RispExp::Nil => {}
+                other => {
+                    return Err(RispErr::Reason(format!(
+                        "unquote-splicing expected a list, got '{other}'"
+                    )))
+                }
+            },
+            _ => res.push(quasiquote(item, env, depth)?),
+        }
+    }
+    Ok(res)
+}
+
+fn eval_read_file(args: &RispList, env: &RispEnv) -> RispResult {
     let key = native_car(args)?;
     let path = match key {
-        RispExp::Symbol(_) => env
+        RispExp::Symbol(..) => env
             .get(&key.to_string())
             .ok_or(RispErr::Reason(format!("unexpected symbol='{key}'")))?,
         _ => key,
     };
-    let file = fs::read_to_string(path.to_string())
-        .ok()
-        .ok_or(RispErr::Reason("can't load file".to_string()))?;
+    let file = fs::read_to_string(display_string(&path))?;
     Ok(RispExp::Literal(file))
 }
 
-fn eval_print(args: &[RispExp], env: &mut RispEnv) -> RispResult {
-    let key = native_car(args)?;
-    let data = match key {
-        RispExp::Symbol(_) => env
-            .get(&key.to_string())
-            .ok_or(RispErr::UnexpectedSymbol(key.to_string()))?,
-        _ => key,
+/// The rest of `exp` if it is a list starting with the symbol `head`, such
+/// as a `(catch e ...)` clause.
+fn clause(exp: Option<&RispExp>, head: &str) -> Option<RispList> {
+    match exp? {
+        RispExp::List(list, _) => match list.first() {
+            Some(RispExp::Symbol(s, _)) if s == head => Some(list.rest()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `(try body... (catch e handler...) (finally cleanup...))` evaluates
+/// `body`, and if it fails evaluates `handler` with `e` bound to the thrown
+/// value or error. `cleanup` runs last whether or not anything failed, and
+/// its value is discarded. Both clauses are optional.
+fn eval_try_arg(args: &RispList, env: &RispEnv) -> RispResult {
+    let last = |len: usize| len.checked_sub(1).and_then(|i| args.get(i));
+    let mut body_len = args.len();
+    let finally = clause(last(body_len), "finally");
+    if finally.is_some() {
+        body_len -= 1;
+    }
+    let catch = clause(last(body_len), "catch");
+    if catch.is_some() {
+        body_len -= 1;
+    }
+    let catch = match catch {
+        Some(catch) => match catch.first() {
+            Some(RispExp::Symbol(name, _)) => Some((name.clone(), catch.rest())),
+            _ => {
+                return Err(RispErr::Reason(
+                    "catch: expected a symbol to bind the error to".to_string(),
+                ))
+            }
+        },
+        None => None,
+    };
+    let res = match (eval_all(args.iter().take(body_len), env), catch) {
+        (Err(err), Some((name, handler))) => {
+            let mut data = HashMap::new();
+            data.insert(name, err.into_value());
+            eval_all(&handler, &env.extend(data))
+        }
+        (res, _) => res,
     };
-    Ok(data)
+    if let Some(cleanup) = finally {
+        eval_all(&cleanup, env)?;
+    }
+    res
 }
 
 fn eval_print_env(env: &RispEnv) -> RispResult {
-    for (k, v) in env.data.iter() {
-        println!("{k}:{v}");
+    let out = current_output();
+    for (k, v) in env.bindings() {
+        out.write_str(&format!("{k}:{v}\n"))?;
     }
     Ok(RispExp::Nil)
 }
 
-pub fn native_car(list: &[RispExp]) -> RispResult {
+pub fn native_car(list: &RispList) -> RispResult {
     Ok(list
         .first()
         .ok_or(RispErr::Reason("expected a non empty list".to_string()))?
         .clone())
 }
 
-pub fn native_cdr(list: &[RispExp]) -> RispResult {
-    if list.len() == 0 {
+/// Shares the tail of `list` rather than copying it.
+pub fn native_cdr(list: &RispList) -> RispResult {
+    if list.is_empty() {
         return Ok(RispExp::Nil);
     }
-    Ok(RispExp::List(list[1..].to_vec()))
+    Ok(RispExp::List(list.rest(), None))
 }
 
-fn eval_load_risp_file(args: &[RispExp], env: &mut RispEnv) -> RispResult {
-    let path = args.first().ok_or(RispErr::InvalidArgs)?;
-    let script = fs::read_to_string(path.to_string())?;
-    Ok(parse_eval(script, env)?)
+fn eval_load_risp_file(args: &RispList, env: &RispEnv) -> RispResult {
+    let path = display_string(args.first().ok_or(RispErr::InvalidArgs)?);
+    let script = fs::read_to_string(&path)?;
+    parse_eval(Source::new(path, script), env)
 }
 
-fn eval_lambda_arg(args: &[RispExp]) -> RispResult {
+fn eval_lambda_arg(args: &RispList, env: &RispEnv) -> RispResult {
     let params = args.first().ok_or(RispErr::InvalidArgs)?;
-    let body = args[1..].to_vec();
+    let body = args.iter().skip(1).cloned().collect();
     Ok(RispExp::Lambda(RispLambda {
         params_exp: Rc::new(params.clone()),
-        body_exp: Rc::new(body.clone()),
+        body_exp: Rc::new(body),
+        env: env.clone(),
     }))
 }
 
-fn eval_if_arg(args: &[RispExp], env: &mut RispEnv) -> RispResult {
-    let arg = args.first().ok_or(RispErr::Reason(
-        "expected first arg to be a bool".to_string(),
+fn eval_defmacro_arg(args: &RispList, env: &RispEnv) -> RispResult {
+    let mut rest = args.iter();
+    let symbol_exp = rest.next().ok_or(RispErr::Reason(
+        "Wrong number of arguments: defmacro, 2".to_string(),
     ))?;
-    match eval(arg, env)? {
-        RispExp::Bool(b) => {
-            let idx = if b { 1 } else { 2 };
-            let result = args
-                .get(idx)
-                .ok_or(RispErr::Reason(format!("expected args idx={idx}")))?;
+    let symbol = match symbol_exp {
+        RispExp::Symbol(s, _) => Ok(s.clone()),
+        _ => Err(RispErr::Reason(
+            "expected first arg to be a symbol".to_string(),
+        )),
+    }?;
+    let params = rest.next().ok_or(RispErr::InvalidArgs)?;
+    let mac = RispLambda {
+        params_exp: Rc::new(params.clone()),
+        body_exp: Rc::new(rest.cloned().collect()),
+        env: env.clone(),
+    };
+    env.define(symbol, RispExp::Macro(mac));
+    Ok(symbol_exp.clone())
+}
 
-            eval(result, env)
+fn eval_macroexpand_arg(args: &RispList, env: &RispEnv, fully: bool) -> RispResult {
+    let [exp] = fixed_args(args, "macroexpand")?;
+    let mut form = eval(exp, env)?;
+    while let Some(expanded) = macroexpand_1(&form, env)? {
+        form = expanded;
+        if !fully {
+            break;
         }
-        _ => Err(RispErr::Reason(format!("unexpected if args = '{arg}'"))),
     }
+    Ok(form)
 }
 
-fn eval_let_arg(args: &[RispExp], env: &mut RispEnv) -> RispResult {
-    let [symbol_exp, value_exp] = <&[RispExp; 2]>::try_from(args).ok().ok_or(RispErr::Reason(
-        "Wrong number of arguments: let, 2".to_string(),
-    ))?;
+/// Expands `exp` once if it is a call to a macro bound in `env`.
+fn macroexpand_1(exp: &RispExp, env: &RispEnv) -> Result<Option<RispExp>, RispErr> {
+    match exp {
+        RispExp::List(list, _) => match macro_for(list, env) {
+            Some(mac) => apply_macro(&mac, &list.rest()).map(Some),
+            None => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// The macro a form calls, if its head is a symbol bound to one.
+fn macro_for(list: &RispList, env: &RispEnv) -> Option<RispLambda> {
+    match list.first() {
+        Some(RispExp::Symbol(s, _)) => match env.get(s) {
+            Some(RispExp::Macro(mac)) => Some(mac),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Runs a macro body with its parameters bound to the unevaluated `args`;
+/// the result is the code that replaces the macro call.
+fn apply_macro(mac: &RispLambda, args: &RispList) -> RispResult {
+    let local_env = env_for_lambda(mac, args.iter().cloned().collect())?;
+    let mut expansion = RispExp::Nil;
+    for exp in mac.body_exp.iter() {
+        expansion = eval(exp, &local_env)?;
+    }
+    Ok(expansion)
+}
+
+fn eval_if_arg(args: &RispList, env: &RispEnv) -> Result<TailCall, RispErr> {
+    if !matches!(args.len(), 2 | 3) {
+        return Err(RispErr::Reason(
+            "Wrong number of arguments: if, 2 or 3".to_string(),
+        ));
+    }
+    let mut items = args.iter();
+    let test = items.next().expect("the length is checked");
+    let idx = if eval(test, env)?.is_truthy() { 0 } else { 1 };
+    match items.nth(idx) {
+        Some(branch) => Ok(TailCall::Eval(branch.clone(), env.clone())),
+        None => Ok(TailCall::Done(RispExp::Nil)),
+    }
+}
+
+/// `(when test body...)` runs the body if `test` is truthy, `unless` if it
+/// is not. Both return nil otherwise.
+fn eval_when_arg(args: &RispList, env: &RispEnv, expected: bool) -> Result<TailCall, RispErr> {
+    let name = if expected { "when" } else { "unless" };
+    let test = args.first().ok_or(RispErr::Reason(format!(
+        "Wrong number of arguments: {name}, 1"
+    )))?;
+    if eval(test, env)?.is_truthy() == expected {
+        eval_body(args.iter().skip(1), env)
+    } else {
+        Ok(TailCall::Done(RispExp::Nil))
+    }
+}
+
+/// `(cond (test body...) ... (else body...))` runs the body of the first
+/// clause whose test is truthy. A clause without a body returns the value
+/// of its test.
+fn eval_cond_arg(args: &RispList, env: &RispEnv) -> Result<TailCall, RispErr> {
+    for clause in args {
+        let (test, items) = match clause {
+            RispExp::List(items, _) if !items.is_empty() => {
+                (items.first().expect("the list is not empty"), items)
+            }
+            _ => {
+                return Err(RispErr::Reason(format!(
+                    "expected cond clause to be a non empty list, got '{clause}'"
+                )))
+            }
+        };
+        let value = match test {
+            RispExp::Symbol(s, _) if s == "else" => RispExp::Bool(true),
+            _ => eval(test, env)?,
+        };
+        if value.is_truthy() {
+            return match items.len() {
+                1 => Ok(TailCall::Done(value)),
+                _ => eval_body(items.iter().skip(1), env),
+            };
+        }
+    }
+    Ok(TailCall::Done(RispExp::Nil))
+}
+
+/// `and` returns the first falsey value and `or` the first truthy one,
+/// without evaluating the rest; otherwise both return their last value.
+fn eval_and_or_arg(args: &RispList, env: &RispEnv, stop_on: bool) -> Result<TailCall, RispErr> {
+    let mut items = args.iter().peekable();
+    while let Some(exp) = items.next() {
+        if items.peek().is_none() {
+            return Ok(TailCall::Eval(exp.clone(), env.clone()));
+        }
+        let value = eval(exp, env)?;
+        if value.is_truthy() == stop_on {
+            return Ok(TailCall::Done(value));
+        }
+    }
+    Ok(TailCall::Done(RispExp::Bool(!stop_on)))
+}
+
+/// Evaluates all but the last form of `body`, which is left to the caller
+/// as a tail call.
+fn eval_body<'a>(
+    body: impl IntoIterator<Item = &'a RispExp>,
+    env: &RispEnv,
+) -> Result<TailCall, RispErr> {
+    let mut body = body.into_iter().peekable();
+    while let Some(exp) = body.next() {
+        if body.peek().is_none() {
+            return Ok(TailCall::Eval(exp.clone(), env.clone()));
+        }
+        eval(exp, env)?;
+    }
+    Ok(TailCall::Done(RispExp::Nil))
+}
+
+fn eval_all<'a>(body: impl IntoIterator<Item = &'a RispExp>, env: &RispEnv) -> RispResult {
+    run(eval_body(body, env)?)
+}
+
+fn eval_do_arg(args: &RispList, env: &RispEnv) -> Result<TailCall, RispErr> {
+    eval_body(args, env)
+}
+
+/// `(def name value)` binds `name` in the current frame, and
+/// `(def (name params...) body...)` is short for defining a lambda.
+fn eval_def_arg(args: &RispList, env: &RispEnv) -> RispResult {
+    if let Some(RispExp::List(signature, _)) = args.first() {
+        let symbol_exp = signature.first().ok_or(RispErr::Reason(
+            "expected a name in the def signature".to_string(),
+        ))?;
+        let symbol = match symbol_exp {
+            RispExp::Symbol(s, _) => Ok(s.clone()),
+            _ => Err(RispErr::Reason(
+                "expected first arg to be a symbol".to_string(),
+            )),
+        }?;
+        let lambda = RispLambda {
+            params_exp: Rc::new(RispExp::List(signature.rest(), None)),
+            body_exp: Rc::new(args.iter().skip(1).cloned().collect()),
+            env: env.clone(),
+        };
+        env.define(symbol, RispExp::Lambda(lambda));
+        return Ok(symbol_exp.clone());
+    }
+    let [symbol_exp, value_exp] = fixed_args(args, "def")?;
     let symbol = match symbol_exp {
-        RispExp::Symbol(s) => Ok(s.clone()),
+        RispExp::Symbol(s, _) => Ok(s.clone()),
         _ => Err(RispErr::Reason(
             "expected first arg to be a symbol".to_string(),
         )),
     }?;
     let value = eval(value_exp, env)?;
-    env.data.insert(symbol, value);
+    env.define(symbol, value);
     Ok(symbol_exp.clone())
 }
 
-fn eval_list(args: &[RispExp], env: &mut RispEnv) -> Result<Vec<RispExp>, RispErr> {
+#[derive(Clone, Copy, PartialEq)]
+enum LetKind {
+    /// Every value is evaluated in the enclosing scope.
+    Let,
+    /// Each value sees the bindings before it.
+    LetStar,
+    /// Every value sees all of the bindings, so they can refer to each
+    /// other recursively.
+    LetRec,
+}
+
+/// `(let ((name value) ...) body...)` evaluates the body in a new scope
+/// holding the bindings.
+fn eval_let_arg(args: &RispList, env: &RispEnv, kind: LetKind) -> Result<TailCall, RispErr> {
+    let bindings_exp = args.first().ok_or(RispErr::Reason(
+        "Wrong number of arguments: let, 1".to_string(),
+    ))?;
+    let bindings = parse_bindings(bindings_exp)?;
+    let local_env = env.extend(HashMap::new());
+    if kind == LetKind::LetRec {
+        for (symbol, _) in &bindings {
+            local_env.define(symbol.clone(), RispExp::Nil);
+        }
+    }
+    for (symbol, value_exp) in bindings {
+        let value = match kind {
+            LetKind::Let => eval(value_exp, env)?,
+            LetKind::LetStar | LetKind::LetRec => eval(value_exp, &local_env)?,
+        };
+        local_env.define(symbol, value);
+    }
+    eval_body(args.iter().skip(1), &local_env)
+}
+
+fn parse_bindings(exp: &RispExp) -> Result<Vec<(String, &RispExp)>, RispErr> {
+    let list = match exp {
+        RispExp::List(list, _) => list,
+        _ => {
+            return Err(RispErr::Reason(format!(
+                "expected a list of bindings such as ((a 1) (b 2)), got '{exp}'; use def to define a variable"
+            )))
+        }
+    };
+    list.iter()
+        .map(|binding| match binding {
+            RispExp::List(pair, _) => {
+                let mut items = pair.iter();
+                match (items.next(), items.next(), items.next()) {
+                    (Some(RispExp::Symbol(s, _)), Some(value), None) => Ok((s.clone(), value)),
+                    _ => Err(RispErr::Reason(format!(
+                        "expected binding to be a (symbol value) pair, got '{binding}'"
+                    ))),
+                }
+            }
+            _ => Err(RispErr::Reason(format!(
+                "expected binding to be a (symbol value) pair, got '{binding}'"
+            ))),
+        })
+        .collect()
+}
+
+fn eval_set_arg(args: &RispList, env: &RispEnv) -> RispResult {
+    let [symbol_exp, value_exp] = fixed_args(args, "set!")?;
+    let symbol = match symbol_exp {
+        RispExp::Symbol(s, _) => Ok(s),
+        _ => Err(RispErr::Reason(
+            "expected first arg to be a symbol".to_string(),
+        )),
+    }?;
+    let value = eval(value_exp, env)?;
+    env.set(symbol, value.clone())?;
+    Ok(value)
+}
+
+fn eval_list(args: &RispList, env: &RispEnv) -> Result<Vec<RispExp>, RispErr> {
     args.iter().map(|x| eval(x, env)).collect()
 }
 
-fn env_for_lambda<'a>(
-    params_exp: Rc<RispExp>,
-    args: &[RispExp],
-    outer_env: &'a mut RispEnv,
-) -> Result<RispEnv<'a>, RispErr> {
-    let symbols = parse_list_of_symbol_strings(params_exp)?;
-    if symbols.len() != args.len() {
+/// Binds the parameters of `lambda` to `values` in a new frame on top of the
+/// env the lambda was defined in. A parameter list such as `(a b & rest)`
+/// collects the remaining values into a list bound to `rest`.
+fn env_for_lambda(lambda: &RispLambda, values: Vec<RispExp>) -> Result<RispEnv, RispErr> {
+    let symbols = parse_list_of_symbol_strings(lambda.params_exp.clone())?;
+    let (fixed, rest) = match symbols.iter().position(|s| s == "&") {
+        Some(idx) => match &symbols[idx + 1..] {
+            [rest] => (&symbols[..idx], Some(rest)),
+            _ => {
+                return Err(RispErr::Reason(
+                    "expected a single parameter after `&`".to_string(),
+                ))
+            }
+        },
+        None => (&symbols[..], None),
+    };
+    if rest.is_none() && fixed.len() != values.len() {
         return Err(RispErr::Reason(format!(
             "expected {} arguments, got {}",
-            symbols.len(),
-            args.len()
+            fixed.len(),
+            values.len()
         )));
     }
-    let values = eval_list(args, outer_env)?;
-    let mut data: HashMap<String, RispExp> = HashMap::new();
-    for (k, v) in symbols.iter().zip(values.iter()) {
-        data.insert(k.clone(), v.clone());
+    if rest.is_some() && fixed.len() > values.len() {
+        return Err(RispErr::Reason(format!(
+            "expected at least {} arguments, got {}",
+            fixed.len(),
+            values.len()
+        )));
+    }
+    let mut values = values;
+    let rest_values = values.split_off(fixed.len());
+    let mut data: HashMap<String, RispExp> = fixed.iter().cloned().zip(values).collect();
+    if let Some(rest) = rest {
+        data.insert(rest.clone(), RispExp::List(rest_values.into(), None));
+    }
+    Ok(lambda.env.extend(data))
+}
+
+/// Reads and evaluates every form in `source`, returning the value of the
+/// last one.
+pub fn parse_eval(source: Rc<Source>, env: &RispEnv) -> RispResult {
+    let tokens = tokenize(&source)?;
+    let mut token = skip_discarded(&tokens)?;
+    let mut result = RispExp::Nil;
+    while !token.is_empty() {
+        let (parsed_exp, remain) = parse(token)?;
+        result = eval(&parsed_exp, env)?;
+        token = skip_discarded(remain)?;
+    }
+    Ok(result)
+}
+
+pub fn eval(exp: &RispExp, env: &RispEnv) -> RispResult {
+    run(eval_step(exp, env)?)
+}
+
+/// Calls a function value on already evaluated arguments, so that builtins
+/// such as `map` accept lambdas as well as other builtins.
+pub fn apply(func: &RispExp, args: Vec<RispExp>, env: &RispEnv) -> RispResult {
+    match func {
+        RispExp::Func(f) => f.call(&args, env),
+        RispExp::Lambda(lambda) => {
+            let local_env = env_for_lambda(lambda, args)?;
+            run(eval_body(lambda.body_exp.iter(), &local_env)?)
+        }
+        _ => Err(RispErr::InvalidFunction(func.to_string())),
     }
-    Ok(RispEnv {
-        data,
-        outer: Some(outer_env),
-    })
 }
 
-pub fn eval(exp: &RispExp, env: &mut RispEnv) -> RispResult {
+fn run(mut tail: TailCall) -> RispResult {
+    loop {
+        match tail {
+            TailCall::Done(value) => return Ok(value),
+            TailCall::Eval(exp, env) => tail = eval_step(&exp, &env)?,
+        }
+    }
+}
+
+fn eval_form(list: &RispList, env: &RispEnv) -> Result<TailCall, RispErr> {
+    let first = list
+        .first()
+        .ok_or(RispErr::Reason("expected a non empty list".to_string()))?;
+    let args = &list.rest();
+    if let Some(mac) = macro_for(list, env) {
+        return Ok(TailCall::Eval(apply_macro(&mac, args)?, env.clone()));
+    }
+    if let Some(res) = eval_tail_form(first, args, env) {
+        return res;
+    }
+    if let Some(res) = eval_built_in_func(first, args, env) {
+        return res.map(TailCall::Done);
+    }
+    let first_eval = eval(first, env)?;
+    match first_eval {
+        RispExp::Func(f) => f.call(&eval_list(args, env)?, env).map(TailCall::Done),
+        RispExp::Lambda(lambda) => {
+            let local_env = env_for_lambda(&lambda, eval_list(args, env)?)?;
+            eval_body(lambda.body_exp.iter(), &local_env)
+        }
+        _ => Err(RispErr::InvalidFunction(first.to_string())),
+    }
+}
+
+fn eval_step(exp: &RispExp, env: &RispEnv) -> Result<TailCall, RispErr> {
     match exp {
-        RispExp::Nil => Ok(exp.clone()),
-        RispExp::Bool(_) => Ok(exp.clone()),
-        RispExp::Number(_) => Ok(exp.clone()),
-        RispExp::Literal(_) => Ok(exp.clone()),
-        RispExp::Symbol(k) => env
+        RispExp::Nil => Ok(TailCall::Done(exp.clone())),
+        RispExp::Bool(_) => Ok(TailCall::Done(exp.clone())),
+        RispExp::Number(_) => Ok(TailCall::Done(exp.clone())),
+        RispExp::Literal(_) => Ok(TailCall::Done(exp.clone())),
+        RispExp::Keyword(_) => Ok(TailCall::Done(exp.clone())),
+        RispExp::Char(_) => Ok(TailCall::Done(exp.clone())),
+        RispExp::Error(_) => Ok(TailCall::Done(exp.clone())),
+        RispExp::Pattern(_) => Ok(TailCall::Done(exp.clone())),
+        RispExp::Port(_) => Ok(TailCall::Done(exp.clone())),
+        RispExp::Symbol(k, span) => env
             .get(k)
-            .ok_or(RispErr::UnexpectedSymbol(k.to_string()))
-            .map(|x| x.clone()),
-        RispExp::List(list) => {
-            let first = list
-                .first()
-                .ok_or(RispErr::Reason("expected a non empty list".to_string()))?;
-            let args = &list[1..];
-            match eval_built_in_func(first, args, env) {
-                Some(res) => res,
-                None => {
-                    let first_eval = eval(first, env)?;
-                    match first_eval {
-                        RispExp::Func(f) => f(&eval_list(args, env)?),
-                        RispExp::Lambda(lambda) => {
-                            let local_env = &mut env_for_lambda(lambda.params_exp, args, env)?;
-                            Ok(eval_list(lambda.body_exp.as_ref(), local_env)?
-                                .last()
-                                .unwrap()
-                                .clone())
-                        }
-                        _ => Err(RispErr::InvalidFunction(first.to_string())),
-                    }
-                }
-            }
+            .map(TailCall::Done)
+            .ok_or_else(|| RispErr::UnexpectedSymbol(k.to_string()).within(span)),
+        RispExp::List(list, span) => eval_form(list, env).map_err(|e| e.within(span)),
+        RispExp::Vector(v) => {
+            let evaled = v.iter().map(|x| eval(x, env)).collect::<Result<_, _>>()?;
+            Ok(TailCall::Done(RispExp::Vector(evaled)))
         }
         RispExp::Func(_) => Err(RispErr::UnexpectedSyntax),
         RispExp::Lambda(_) => Err(RispErr::UnexpectedSyntax),
+        RispExp::Macro(_) => Err(RispErr::UnexpectedSyntax),
+        RispExp::Map(map, literal) => {
+            eval_map(map_forms(map, literal), env).map_err(|e| e.within(&literal_span(literal)))
+        }
+        RispExp::Set(set, literal) => {
+            eval_set(set_forms(set, literal), env).map_err(|e| e.within(&literal_span(literal)))
+        }
+    }
+}
+
+fn literal_span(literal: &Option<Rc<LiteralForms>>) -> Option<Span> {
+    literal.as_ref().map(|literal| literal.span.clone())
+}
+
+/// The keys and values of a map in the order they were written, if it is a
+/// literal.
+fn map_forms<'a>(
+    map: &'a im_rc::HashMap<RispExp, RispExp>,
+    literal: &'a Option<Rc<LiteralForms>>,
+) -> Vec<&'a RispExp> {
+    match literal {
+        Some(literal) => literal.forms.iter().collect(),
+        None => map.iter().flat_map(|(k, v)| [k, v]).collect(),
+    }
+}
+
+/// The items of a set in the order they were written, if it is a literal.
+fn set_forms<'a>(
+    set: &'a im_rc::HashSet<RispExp>,
+    literal: &'a Option<Rc<LiteralForms>>,
+) -> Vec<&'a RispExp> {
+    match literal {
+        Some(literal) => literal.forms.iter().collect(),
+        None => set.iter().collect(),
+    }
+}
+
+/// Adds a key of a map literal. Keys that evaluate to the same value are an
+/// error, as they are when written the same.
+fn insert_key(
+    map: &mut im_rc::HashMap<RispExp, RispExp>,
+    key: RispExp,
+    value: RispExp,
+) -> Result<(), RispErr> {
+    if map.contains_key(&key) {
+        return Err(RispErr::Reason(format!(
+            "duplicate key '{key}' in map literal"
+        )));
+    }
+    map.insert(key, value);
+    Ok(())
+}
+
+fn insert_element(set: &mut im_rc::HashSet<RispExp>, item: RispExp) -> Result<(), RispErr> {
+    if set.contains(&item) {
+        return Err(RispErr::Reason(format!(
+            "duplicate element '{item}' in set literal"
+        )));
+    }
+    set.insert(item);
+    Ok(())
+}
+
+/// Evaluates the keys and values of a map literal in the order they were
+/// written.
+fn eval_map(forms: Vec<&RispExp>, env: &RispEnv) -> Result<TailCall, RispErr> {
+    let mut evaled = im_rc::HashMap::new();
+    for pair in forms.chunks(2) {
+        let key = eval(pair[0], env)?;
+        let value = eval(pair[1], env)?;
+        insert_key(&mut evaled, key, value)?;
+    }
+    Ok(TailCall::Done(RispExp::Map(evaled, None)))
+}
+
+/// Evaluates the items of a set literal in the order they were written.
+fn eval_set(forms: Vec<&RispExp>, env: &RispEnv) -> Result<TailCall, RispErr> {
+    let mut evaled = im_rc::HashSet::new();
+    for form in forms {
+        insert_element(&mut evaled, eval(form, env)?)?;
+    }
+    Ok(TailCall::Done(RispExp::Set(evaled, None)))
+}
+
+/// The map a quasiquoted map literal builds from its keys and values, which
+/// splicing may have left without a value for the last key.
+fn build_map(items: Vec<RispExp>) -> RispResult {
+    if !items.len().is_multiple_of(2) {
+        return Err(RispErr::Reason(
+            "map literal must contain an even number of forms".to_string(),
+        ));
+    }
+    let mut map = im_rc::HashMap::new();
+    let mut items = items.into_iter();
+    while let (Some(key), Some(value)) = (items.next(), items.next()) {
+        insert_key(&mut map, key, value)?;
+    }
+    Ok(RispExp::Map(map, None))
+}
+
+fn build_set(items: Vec<RispExp>) -> RispResult {
+    let mut set = im_rc::HashSet::new();
+    for item in items {
+        insert_element(&mut set, item)?;
     }
+    Ok(RispExp::Set(set, None))
 }
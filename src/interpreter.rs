@@ -0,0 +1,109 @@
+use crate::convert::{IntoRisp, NativeFunction};
+use crate::eval::{apply, parse_eval};
+use crate::parser::standard_env;
+use crate::pretty::{pretty, PrettyOptions};
+use crate::risp_type::*;
+use crate::span::Source;
+use std::fs;
+use std::path::Path;
+
+/// An interpreter for embedding Risp in a Rust program. It owns a global
+/// environment holding the standard library, to which the host can add its
+/// own values and functions.
+///
+/// Methods take `&self` because the environment is shared: cloning an
+/// `Interpreter` gives another handle to the same globals.
+#[derive(Clone)]
+pub struct Interpreter {
+    env: RispEnv,
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter {
+            env: standard_env(),
+        }
+    }
+
+    /// Evaluates every form in `source`, returning the value of the last one.
+    pub fn eval_str(&self, source: &str) -> RispResult {
+        self.eval_source("<string>", source)
+    }
+
+    /// Like `eval_str`, with `name` shown as the location in error messages.
+    pub fn eval_source(&self, name: &str, source: &str) -> RispResult {
+        parse_eval(Source::new(name, source), &self.env)
+    }
+
+    pub fn eval_file(&self, path: impl AsRef<Path>) -> RispResult {
+        let path = path.as_ref();
+        let script = fs::read_to_string(path)?;
+        parse_eval(Source::new(path.display().to_string(), script), &self.env)
+    }
+
+    pub fn define_global(&self, name: &str, value: impl IntoRisp) {
+        self.env.define(name.to_string(), value.into_risp());
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<RispExp> {
+        self.env.get(name)
+    }
+
+    /// Defines a global function backed by a Rust closure. The arguments
+    /// are converted from Risp values with `FromRisp`, and the result back
+    /// with `IntoRisp`; the closure's parameter types pick the conversions.
+    ///
+    /// ```
+    /// use risp::Interpreter;
+    ///
+    /// let interp = Interpreter::new();
+    /// interp.register_fn("hypot", |a: f64, b: f64| (a * a + b * b).sqrt());
+    /// let res = interp.eval_str("(hypot 3 4)").unwrap();
+    /// assert_eq!(res.to_string(), "5.0");
+    /// ```
+    pub fn register_fn<Args>(&self, name: &str, func: impl NativeFunction<Args>) {
+        self.register_native(func.into_func(name));
+    }
+
+    /// Defines a global function that works on Risp values directly.
+    pub fn register_native(&self, func: RispFunc) {
+        self.env.define(func.name.to_string(), RispExp::Func(func));
+    }
+
+    /// Calls the global function `name`, which may be a builtin or a lambda
+    /// defined in Risp.
+    ///
+    /// ```
+    /// use risp::{Interpreter, IntoRisp};
+    ///
+    /// let interp = Interpreter::new();
+    /// interp.eval_str("(def (square x) (* x x))").unwrap();
+    /// let res = interp.call_function("square", vec![12.into_risp()]).unwrap();
+    /// assert_eq!(res.to_string(), "144");
+    /// ```
+    pub fn call_function(&self, name: &str, args: Vec<RispExp>) -> RispResult {
+        let func = self
+            .env
+            .get(name)
+            .ok_or(RispErr::UnexpectedSymbol(name.to_string()))?;
+        apply(&func, args, &self.env)
+    }
+
+    /// Pretty prints `exp` as the REPL shows results, laid out according to
+    /// `*print-width*` and `*print-indent*` and abbreviated according to
+    /// `*print-length*` and `*print-level*`.
+    pub fn pretty_print(&self, exp: &RispExp) -> String {
+        pretty(exp, &PrettyOptions::from_env(&self.env))
+    }
+
+    /// The global environment.
+    pub fn env(&self) -> &RispEnv {
+        &self.env
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
+}
@@ -0,0 +1,447 @@
+use crate::atom;
+use crate::risp_type::*;
+use crate::span::*;
+use std::iter::Peekable;
+use std::rc::Rc;
+use std::str::CharIndices;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    LParen,
+    RParen,
+    /// `{`, `#{` and `}`, which delimit map and set literals.
+    LBrace,
+    HashBrace,
+    RBrace,
+    /// `[` and `]`, which delimit vector literals.
+    LBracket,
+    RBracket,
+    Str(String),
+    /// A `#\a`, `#\newline` or `#\space` character literal.
+    Char(char),
+    /// The source of a `#"..."` regular expression literal.
+    Regex(String),
+    Atom(String),
+    /// `#_`, which makes the reader skip the next datum.
+    Discard,
+    /// `'`, `` ` ``, `,` and `,@`, which the reader expands to `(quote x)`,
+    /// `(quasiquote x)`, `(unquote x)` and `(unquote-splicing x)`.
+    Quote,
+    Quasiquote,
+    Unquote,
+    UnquoteSplicing,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TriviaKind {
+    LineComment,
+    BlockComment,
+}
+
+/// A comment, kept on the token next to it so that tools working on the
+/// token stream (such as formatters) can reproduce it. `text` includes the
+/// comment delimiters.
+#[derive(Clone, Debug)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub span: Span,
+}
+
+/// Comments on the lines before a token are its `leading` trivia; comments
+/// after it on the same line, and any comments at the end of the source,
+/// are `trailing` trivia of the token before them.
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+    pub leading: Vec<Trivia>,
+    pub trailing: Vec<Trivia>,
+}
+
+pub fn tokenize(source: &Rc<Source>) -> Result<Vec<Token>, RispErr> {
+    let mut lexer = Lexer::new(source);
+    let mut tokens = TokenSink::default();
+    while let Some(c) = lexer.peek() {
+        let start = lexer.mark();
+        let kind = match c {
+            ';' => {
+                let text = lexer.read_line_comment();
+                tokens.comment(TriviaKind::LineComment, text, lexer.span_from(&start));
+                continue;
+            }
+            '#' if lexer.peek_second() == Some('|') => {
+                let text = lexer.read_block_comment(&start)?;
+                tokens.comment(TriviaKind::BlockComment, text, lexer.span_from(&start));
+                continue;
+            }
+            '#' if lexer.peek_second() == Some('{') => {
+                lexer.bump();
+                lexer.bump();
+                TokenKind::HashBrace
+            }
+            '{' => {
+                lexer.bump();
+                TokenKind::LBrace
+            }
+            '}' => {
+                lexer.bump();
+                TokenKind::RBrace
+            }
+            '#' if lexer.peek_second() == Some('\\') => {
+                lexer.bump();
+                lexer.bump();
+                TokenKind::Char(lexer.read_char(&start)?)
+            }
+            '#' if lexer.peek_second() == Some('"') => {
+                lexer.bump();
+                lexer.bump();
+                TokenKind::Regex(lexer.read_regex(&start)?)
+            }
+            '#' if lexer.peek_second() == Some('_') => {
+                lexer.bump();
+                lexer.bump();
+                TokenKind::Discard
+            }
+            '[' => {
+                lexer.bump();
+                TokenKind::LBracket
+            }
+            ']' => {
+                lexer.bump();
+                TokenKind::RBracket
+            }
+            '(' => {
+                lexer.bump();
+                TokenKind::LParen
+            }
+            ')' => {
+                lexer.bump();
+                TokenKind::RParen
+            }
+            '"' => {
+                lexer.bump();
+                TokenKind::Str(lexer.read_string(&start)?)
+            }
+            '#' if lexer.read_lambda_shorthand() => {
+                tokens.push(TokenKind::LParen, lexer.span_from(&start), lexer.line);
+                TokenKind::Atom("fn".to_string())
+            }
+            '\'' => {
+                lexer.bump();
+                TokenKind::Quote
+            }
+            '`' => {
+                lexer.bump();
+                TokenKind::Quasiquote
+            }
+            ',' => {
+                lexer.bump();
+                if lexer.peek() == Some('@') {
+                    lexer.bump();
+                    TokenKind::UnquoteSplicing
+                } else {
+                    TokenKind::Unquote
+                }
+            }
+            c if c.is_whitespace() => {
+                lexer.bump();
+                continue;
+            }
+            _ => TokenKind::Atom(lexer.read_atom()),
+        };
+        tokens.push(kind, lexer.span_from(&start), lexer.line);
+    }
+    Ok(tokens.finish())
+}
+
+#[derive(Default)]
+struct TokenSink {
+    tokens: Vec<Token>,
+    pending: Vec<Trivia>,
+    last_line: usize,
+}
+
+impl TokenSink {
+    fn push(&mut self, kind: TokenKind, span: Span, end_line: usize) {
+        self.tokens.push(Token {
+            kind,
+            span,
+            leading: std::mem::take(&mut self.pending),
+            trailing: vec![],
+        });
+        self.last_line = end_line;
+    }
+
+    fn comment(&mut self, kind: TriviaKind, text: String, span: Span) {
+        let same_line = span.line == self.last_line;
+        let trivia = Trivia { kind, text, span };
+        match self.tokens.last_mut() {
+            Some(last) if same_line && self.pending.is_empty() => last.trailing.push(trivia),
+            _ => self.pending.push(trivia),
+        }
+    }
+
+    fn finish(mut self) -> Vec<Token> {
+        if let Some(last) = self.tokens.last_mut() {
+            last.trailing.append(&mut self.pending);
+        }
+        self.tokens
+    }
+}
+
+/// A position in the source, used to build the span of a token once its
+/// end is known.
+struct Mark {
+    offset: usize,
+    line: usize,
+    col: usize,
+}
+
+struct Lexer<'a> {
+    source: &'a Rc<Source>,
+    chars: Peekable<CharIndices<'a>>,
+    line: usize,
+    col: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a Rc<Source>) -> Lexer<'a> {
+        Lexer {
+            source,
+            chars: source.text.char_indices().peekable(),
+            line: 1,
+            col: 1,
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.chars.clone().nth(1).map(|(_, c)| c)
+    }
+
+    fn offset(&mut self) -> usize {
+        self.chars
+            .peek()
+            .map_or(self.source.text.len(), |&(i, _)| i)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let (_, c) = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    fn mark(&mut self) -> Mark {
+        Mark {
+            offset: self.offset(),
+            line: self.line,
+            col: self.col,
+        }
+    }
+
+    fn span_from(&mut self, start: &Mark) -> Span {
+        Span {
+            source: self.source.clone(),
+            start: start.offset,
+            end: self.offset(),
+            line: start.line,
+            col: start.col,
+        }
+    }
+
+    fn error_from(&mut self, start: &Mark, err: RispErr) -> RispErr {
+        err.at(self.span_from(start))
+    }
+
+    /// `#'(x)` reads as `(fn x)`. Consumes the prefix if it starts here.
+    fn read_lambda_shorthand(&mut self) -> bool {
+        let mut ahead = self.chars.clone().map(|(_, c)| c);
+        if (ahead.next(), ahead.next(), ahead.next()) != (Some('#'), Some('\''), Some('(')) {
+            return false;
+        }
+        for _ in 0..3 {
+            self.bump();
+        }
+        true
+    }
+
+    /// Reads a `;` comment up to, but not including, the end of the line.
+    fn read_line_comment(&mut self) -> String {
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                break;
+            }
+            text.push(c);
+            self.bump();
+        }
+        text
+    }
+
+    /// Reads a `#| ... |#` comment, which may contain nested block comments.
+    fn read_block_comment(&mut self, start: &Mark) -> Result<String, RispErr> {
+        let mut text = String::new();
+        let mut depth = 0;
+        loop {
+            match (self.peek(), self.peek_second()) {
+                (Some('#'), Some('|')) => depth += 1,
+                (Some('|'), Some('#')) => depth -= 1,
+                (Some(_), _) => {
+                    text.extend(self.bump());
+                    continue;
+                }
+                (None, _) => {
+                    let err = RispErr::Reason("unterminated block comment".to_string());
+                    return Err(self.error_from(start, err));
+                }
+            }
+            text.extend(self.bump());
+            text.extend(self.bump());
+            if depth == 0 {
+                return Ok(text);
+            }
+        }
+    }
+
+    fn read_atom(&mut self) -> String {
+        let mut atom = String::new();
+        while let Some(c) = self.peek() {
+            if is_delimiter(c) {
+                break;
+            }
+            atom.push(c);
+            self.bump();
+        }
+        atom
+    }
+
+    /// Reads the character after `#\`, which runs to the next delimiter
+    /// like any atom and must be a single character or a name such as
+    /// `newline`. The first character may itself be a delimiter, so `#\(`
+    /// is an open paren rather than the start of a list.
+    fn read_char(&mut self, start: &Mark) -> Result<char, RispErr> {
+        let first = match self.bump() {
+            Some(c) => c,
+            None => {
+                let err = RispErr::Reason("expected a character after `#\\`".to_string());
+                return Err(self.error_from(start, err));
+            }
+        };
+        let rest = self.read_atom();
+        if rest.is_empty() {
+            return Ok(first);
+        }
+        let name = format!("{first}{rest}");
+        match atom::char_from_name(&name) {
+            Some(c) => Ok(c),
+            None => {
+                let err = RispErr::Reason(format!("unknown character name `#\\{name}`"));
+                Err(self.error_from(start, err))
+            }
+        }
+    }
+
+    /// Reads the body of a string literal; the opening `"` is already
+    /// consumed.
+    fn read_string(&mut self, start: &Mark) -> Result<String, RispErr> {
+        let mut literal = String::new();
+        loop {
+            let escape = self.mark();
+            let c = match self.bump() {
+                Some(c) => c,
+                None => {
+                    let err = RispErr::Reason("unterminated string literal".to_string());
+                    return Err(self.error_from(start, err));
+                }
+            };
+            match c {
+                '"' => return Ok(literal),
+                '\\' => match self.read_escape() {
+                    Ok(c) => literal.push(c),
+                    Err(err) => return Err(self.error_from(&escape, err)),
+                },
+                _ => literal.push(c),
+            }
+        }
+    }
+
+    /// Reads the body of a `#"..."` literal; the opening `#"` is already
+    /// consumed. Backslashes are kept for the regex syntax to interpret,
+    /// except that `\"` stands for a quote.
+    fn read_regex(&mut self, start: &Mark) -> Result<String, RispErr> {
+        let mut source = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(source),
+                Some('\\') if self.peek() == Some('"') => {
+                    source.extend(self.bump());
+                }
+                Some('\\') => {
+                    source.push('\\');
+                    source.extend(self.bump());
+                }
+                Some(c) => source.push(c),
+                None => {
+                    let err = RispErr::Reason("unterminated regex literal".to_string());
+                    return Err(self.error_from(start, err));
+                }
+            }
+        }
+    }
+
+    fn read_escape(&mut self) -> Result<char, RispErr> {
+        let c = self
+            .bump()
+            .ok_or(RispErr::Reason("unterminated string literal".to_string()))?;
+        match c {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '"' => Ok('"'),
+            '\\' => Ok('\\'),
+            'u' => self.read_unicode_escape(),
+            _ => Err(RispErr::Reason(format!("unknown escape sequence `\\{c}`"))),
+        }
+    }
+
+    /// Reads the `{...}` part of a `\u{...}` escape.
+    fn read_unicode_escape(&mut self) -> Result<char, RispErr> {
+        if self.bump() != Some('{') {
+            return Err(RispErr::Reason(
+                "expected `{` after `\\u` in string literal".to_string(),
+            ));
+        }
+        let mut hex = String::new();
+        loop {
+            match self.bump() {
+                Some('}') => break,
+                Some(c) if c.is_ascii_hexdigit() && hex.len() < 6 => hex.push(c),
+                _ => {
+                    return Err(RispErr::Reason(
+                        "invalid `\\u{...}` escape in string literal".to_string(),
+                    ))
+                }
+            }
+        }
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(RispErr::Reason(format!(
+                "invalid unicode scalar value `\\u{{{hex}}}`"
+            )))
+    }
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '(' | ')' | '[' | ']' | '{' | '}' | '"' | ';' | '`' | ',')
+}
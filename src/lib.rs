@@ -0,0 +1,34 @@
+//! Risp, a small Lisp. The [`Interpreter`] type is the entry point for
+//! embedding it: evaluate source with `eval_str` or `eval_file`, expose host
+//! values with `define_global` and Rust closures with `register_fn`, and
+//! call back into Risp with `call_function`.
+
+mod collections;
+mod errors;
+mod interpreter;
+mod lists;
+mod regexes;
+mod strings;
+
+pub mod atom;
+pub mod convert;
+pub mod de;
+pub mod eval;
+pub mod lexer;
+pub mod list;
+pub mod number;
+pub mod parser;
+pub mod pattern;
+pub mod port;
+pub mod pretty;
+pub mod printer;
+pub mod risp_type;
+pub mod ser;
+pub mod span;
+
+pub use convert::{FromRisp, IntoRisp};
+pub use de::from_value;
+pub use interpreter::Interpreter;
+pub use number::Number;
+pub use risp_type::{Arity, RispEnv, RispErr, RispError, RispExp, RispFunc, RispResult};
+pub use ser::to_value;
@@ -0,0 +1,149 @@
+use crate::risp_type::RispExp;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::rc::Rc;
+
+/// An immutable singly linked list. Lists share their tails, so `cons` and
+/// `rest` are O(1) and never copy elements.
+#[derive(Clone, Default)]
+pub struct RispList {
+    head: Option<Rc<Node>>,
+    len: usize,
+}
+
+struct Node {
+    first: RispExp,
+    rest: RispList,
+}
+
+impl RispList {
+    pub fn new() -> RispList {
+        RispList::default()
+    }
+
+    /// A new list with `first` in front of `self`.
+    pub fn cons(&self, first: RispExp) -> RispList {
+        RispList {
+            len: self.len + 1,
+            head: Some(Rc::new(Node {
+                first,
+                rest: self.clone(),
+            })),
+        }
+    }
+
+    pub fn first(&self) -> Option<&RispExp> {
+        self.head.as_ref().map(|node| &node.first)
+    }
+
+    /// Everything after the first element; empty if the list is.
+    pub fn rest(&self) -> RispList {
+        match &self.head {
+            Some(node) => node.rest.clone(),
+            None => RispList::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Walks the list, so this is O(index).
+    pub fn get(&self, index: usize) -> Option<&RispExp> {
+        self.iter().nth(index)
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+
+    /// Whether both lists are the same nodes in memory. All empty lists are
+    /// the same list.
+    pub fn ptr_eq(&self, other: &RispList) -> bool {
+        match (&self.head, &other.head) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<RispExp> {
+        self.iter().cloned().collect()
+    }
+}
+
+pub struct Iter<'a> {
+    next: Option<&'a Node>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a RispExp;
+
+    fn next(&mut self) -> Option<&'a RispExp> {
+        let node = self.next?;
+        self.next = node.rest.head.as_deref();
+        Some(&node.first)
+    }
+}
+
+impl<'a> IntoIterator for &'a RispList {
+    type Item = &'a RispExp;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl FromIterator<RispExp> for RispList {
+    fn from_iter<I: IntoIterator<Item = RispExp>>(iter: I) -> RispList {
+        let items: Vec<RispExp> = iter.into_iter().collect();
+        items
+            .into_iter()
+            .rev()
+            .fold(RispList::new(), |list, x| list.cons(x))
+    }
+}
+
+impl From<Vec<RispExp>> for RispList {
+    fn from(items: Vec<RispExp>) -> RispList {
+        items.into_iter().collect()
+    }
+}
+
+/// Unlinks the nodes one at a time, since the default recursive drop would
+/// overflow the stack on long lists.
+impl Drop for RispList {
+    fn drop(&mut self) {
+        let mut head = self.head.take();
+        while let Some(node) = head {
+            match Rc::try_unwrap(node) {
+                Ok(mut node) => head = node.rest.head.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+impl PartialEq for RispList {
+    fn eq(&self, other: &RispList) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for RispList {}
+
+impl Hash for RispList {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len.hash(state);
+        for x in self {
+            x.hash(state);
+        }
+    }
+}
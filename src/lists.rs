@@ -0,0 +1,285 @@
+use crate::eval::apply;
+use crate::list::RispList;
+use crate::number::Number;
+use crate::risp_type::*;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Lists, vectors and nil can all be walked as lists; vectors are copied.
+fn parse_seq(exp: &RispExp, name: &str) -> Result<RispList, RispErr> {
+    match exp {
+        RispExp::List(list, _) => Ok(list.clone()),
+        RispExp::Vector(v) => Ok(v.iter().cloned().collect()),
+        RispExp::Nil => Ok(RispList::new()),
+        _ => Err(RispErr::Type(format!(
+            "{name}: expected a list, got '{exp}'"
+        ))),
+    }
+}
+
+fn list(items: impl IntoIterator<Item = RispExp>) -> RispExp {
+    RispExp::List(items.into_iter().collect(), None)
+}
+
+/// Calls `func` on the first items of each of `seqs`, then the second ones
+/// and so on, stopping at the end of the shortest.
+fn map_each(
+    seqs: &[RispList],
+    mut func: impl FnMut(Vec<RispExp>) -> Result<(), RispErr>,
+) -> Result<(), RispErr> {
+    let mut iters: Vec<_> = seqs.iter().map(|seq| seq.iter()).collect();
+    loop {
+        let args: Option<Vec<RispExp>> = iters.iter_mut().map(|it| it.next().cloned()).collect();
+        match args {
+            Some(args) => func(args)?,
+            None => return Ok(()),
+        }
+    }
+}
+
+/// The order `sort` uses without a comparator: numbers numerically and
+/// strings, characters, keywords and symbols by their text.
+fn natural_cmp(a: &RispExp, b: &RispExp) -> Result<Ordering, RispErr> {
+    let ordering = match (a, b) {
+        (RispExp::Number(x), RispExp::Number(y)) => x.num_cmp(y),
+        (RispExp::Literal(x), RispExp::Literal(y)) => Some(x.cmp(y)),
+        (RispExp::Char(x), RispExp::Char(y)) => Some(x.cmp(y)),
+        (RispExp::Keyword(x), RispExp::Keyword(y)) => Some(x.name().cmp(y.name())),
+        (RispExp::Symbol(x, _), RispExp::Symbol(y, _)) => Some(x.cmp(y)),
+        _ => None,
+    };
+    ordering.ok_or(RispErr::Type(format!(
+        "sort: cannot compare '{a}' and '{b}'"
+    )))
+}
+
+/// A stable merge sort driven by a fallible "less than" predicate, which
+/// `slice::sort_by` cannot take.
+fn merge_sort(
+    items: Vec<RispExp>,
+    less: &mut dyn FnMut(&RispExp, &RispExp) -> Result<bool, RispErr>,
+) -> Result<Vec<RispExp>, RispErr> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+    let mut left = items;
+    let right = left.split_off(left.len() / 2);
+    let left = merge_sort(left, less)?;
+    let right = merge_sort(right, less)?;
+    let mut res = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        // Only take from the right when it is strictly less, to keep equal
+        // items in their original order.
+        if less(r, l)? {
+            res.extend(right.next());
+        } else {
+            res.extend(left.next());
+        }
+    }
+    res.extend(left);
+    res.extend(right);
+    Ok(res)
+}
+
+fn flatten_into(exp: &RispExp, res: &mut Vec<RispExp>) {
+    match exp {
+        RispExp::List(list, _) => list.iter().for_each(|x| flatten_into(x, res)),
+        RispExp::Vector(v) => v.iter().for_each(|x| flatten_into(x, res)),
+        RispExp::Nil => {}
+        _ => res.push(exp.clone()),
+    }
+}
+
+/// Adds the list processing builtins to a standard environment.
+pub fn insert_builtins(data: &mut HashMap<String, RispExp>) {
+    insert_func(
+        data,
+        RispFunc::fixed("cons", |[first, rest], _| {
+            Ok(RispExp::List(
+                parse_seq(rest, "cons")?.cons(first.clone()),
+                None,
+            ))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("append", Arity::AtLeast(0), |args, _| {
+            // The last list is shared rather than copied.
+            let (last, init) = match args.split_last() {
+                Some(split) => split,
+                None => return Ok(list(vec![])),
+            };
+            let mut res = parse_seq(last, "append")?;
+            for seq in init.iter().rev() {
+                let items = parse_seq(seq, "append")?.to_vec();
+                res = items.into_iter().rev().fold(res, |res, x| res.cons(x));
+            }
+            Ok(RispExp::List(res, None))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("reverse", |[seq], _| {
+            let reversed = parse_seq(seq, "reverse")?
+                .iter()
+                .fold(RispList::new(), |res, x| res.cons(x.clone()));
+            Ok(RispExp::List(reversed, None))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("length", |[seq], _| {
+            let len = match seq {
+                RispExp::Vector(v) => v.len(),
+                _ => parse_seq(seq, "length")?.len(),
+            };
+            Ok(RispExp::Number(Number::Int(len as i64)))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("map", Arity::AtLeast(2), |args, env| {
+            let (func, seqs) = args.split_first().expect("arity is checked by call");
+            let seqs = seqs
+                .iter()
+                .map(|seq| parse_seq(seq, "map"))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut res = vec![];
+            map_each(&seqs, |args| {
+                res.push(apply(func, args, env)?);
+                Ok(())
+            })?;
+            Ok(list(res))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("for-each", Arity::AtLeast(2), |args, env| {
+            let (func, seqs) = args.split_first().expect("arity is checked by call");
+            let seqs = seqs
+                .iter()
+                .map(|seq| parse_seq(seq, "for-each"))
+                .collect::<Result<Vec<_>, _>>()?;
+            map_each(&seqs, |args| apply(func, args, env).map(|_| ()))?;
+            Ok(RispExp::Nil)
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("filter", |[pred, seq], env| {
+            let mut res = vec![];
+            for x in parse_seq(seq, "filter")?.iter() {
+                if apply(pred, vec![x.clone()], env)?.is_truthy() {
+                    res.push(x.clone());
+                }
+            }
+            Ok(list(res))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("reduce", Arity::Between(2, 3), |args, env| {
+            // `(reduce f list)` starts from the first item and
+            // `(reduce f init list)` from `init`; `f` takes the accumulated
+            // value first.
+            let (func, init, seq) = match args {
+                [func, seq] => {
+                    let seq = parse_seq(seq, "reduce")?;
+                    let init = seq.first().cloned().ok_or(RispErr::Reason(
+                        "reduce: empty list and no initial value".to_string(),
+                    ))?;
+                    (func, init, seq.rest())
+                }
+                [func, init, seq] => (func, init.clone(), parse_seq(seq, "reduce")?),
+                _ => unreachable!("arity is checked by call"),
+            };
+            seq.iter()
+                .try_fold(init, |acc, x| apply(func, vec![acc, x.clone()], env))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("fold", |[func, init, seq], env| {
+            parse_seq(seq, "fold")?
+                .iter()
+                .try_fold(init.clone(), |acc, x| {
+                    apply(func, vec![acc, x.clone()], env)
+                })
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("apply", Arity::AtLeast(2), |args, env| {
+            // `(apply f a b list)` calls `f` with `a`, `b` and the items of
+            // `list`.
+            let (last, init) = args.split_last().expect("arity is checked by call");
+            let (func, init) = init.split_first().expect("arity is checked by call");
+            let mut call_args = init.to_vec();
+            call_args.extend(parse_seq(last, "apply")?.iter().cloned());
+            apply(func, call_args, env)
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("sort", Arity::Between(1, 2), |args, env| {
+            // The comparator is a "less than" predicate such as `<`.
+            let (seq, less) = match args {
+                [seq] => (seq, None),
+                [seq, less] => (seq, Some(less)),
+                _ => unreachable!("arity is checked by call"),
+            };
+            let items = parse_seq(seq, "sort")?.to_vec();
+            let sorted = match less {
+                Some(less) => merge_sort(items, &mut |a, b| {
+                    Ok(apply(less, vec![a.clone(), b.clone()], env)?.is_truthy())
+                })?,
+                None => merge_sort(items, &mut |a, b| Ok(natural_cmp(a, b)? == Ordering::Less))?,
+            };
+            Ok(list(sorted))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("take", |[n, seq], _| {
+            let n = parse_index(n, "take")?;
+            Ok(list(parse_seq(seq, "take")?.iter().take(n).cloned()))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("drop", |[n, seq], _| {
+            let n = parse_index(n, "drop")?;
+            // Shares the remaining tail.
+            let mut rest = parse_seq(seq, "drop")?;
+            for _ in 0..n.min(rest.len()) {
+                rest = rest.rest();
+            }
+            Ok(RispExp::List(rest, None))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("zip", Arity::AtLeast(1), |args, _| {
+            let seqs = args
+                .iter()
+                .map(|seq| parse_seq(seq, "zip"))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut res = vec![];
+            map_each(&seqs, |items| {
+                res.push(list(items));
+                Ok(())
+            })?;
+            Ok(list(res))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("flatten", |[seq], _| {
+            let mut res = vec![];
+            flatten_into(seq, &mut res);
+            Ok(list(res))
+        }),
+    );
+}
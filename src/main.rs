@@ -1,21 +1,14 @@
-mod eval;
-mod parser;
 mod repl;
-mod risp_type;
 
-use crate::risp_type::*;
 use anyhow::Result;
-use clap::App;
-use parser::standard_env;
-use repl::parse_eval;
-use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use risp::Interpreter;
+use rustyline::completion::FilenameCompleter;
 use rustyline::config::OutputStreamType;
 use rustyline::error::ReadlineError;
-use rustyline::highlight::{Highlighter, MatchingBracketHighlighter};
-use rustyline::hint::{Hinter, HistoryHinter};
-use rustyline::validate::{self, MatchingBracketValidator, Validator};
-use rustyline::{Cmd, CompletionType, Config, Context, EditMode, Editor, KeyEvent};
-use rustyline_derive::Helper;
+use rustyline::highlight::MatchingBracketHighlighter;
+use rustyline::hint::HistoryHinter;
+use rustyline::validate::MatchingBracketValidator;
+use rustyline::{Cmd, CompletionType, Config, EditMode, Editor, KeyEvent};
 
 fn main() -> Result<()> {
     env_logger::init();
@@ -40,7 +33,7 @@ fn main() -> Result<()> {
         println!("No previous history.");
     }
     let mut count = 1;
-    let mut std_env = standard_env();
+    let interp = Interpreter::new();
     loop {
         let p = format!("{}> ", count);
         rl.helper_mut().expect("No helper").colored_prompt = format!("\x1b[1;32m{}\x1b[0m", p);
@@ -48,8 +41,8 @@ fn main() -> Result<()> {
         match readline {
             Ok(line) => {
                 rl.add_history_entry(line.as_str());
-                match parse_eval(line, &mut std_env) {
-                    Ok(res) => println!("// 🔥 => {}", res),
+                match interp.eval_source("<repl>", &line) {
+                    Ok(res) => println!("// 🔥 => {}", interp.pretty_print(&res)),
                     Err(e) => println!("// 🙀 => {e}"),
                 }
             }
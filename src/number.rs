@@ -0,0 +1,358 @@
+use crate::risp_type::*;
+use core::fmt;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, One, Signed, ToPrimitive};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::{Add, Mul, Sub};
+use std::rc::Rc;
+
+/// Risp's numeric tower. Exact integers are stored as `Int` while they fit
+/// in an `i64` and promoted to `Big` when they do not; exact division
+/// produces `Ratio`s. Any operation involving a `Float` is inexact.
+///
+/// Constructors normalise their result, so an integral ratio is always an
+/// integer and an integer that fits in an `i64` is always an `Int`.
+#[derive(Clone, Debug)]
+pub enum Number {
+    Int(i64),
+    Big(Rc<BigInt>),
+    Ratio(Rc<BigRational>),
+    Float(f64),
+}
+
+/// How far up the tower a number is; mixed operations happen at the
+/// higher of the two levels.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    Integer,
+    Ratio,
+    Float,
+}
+
+impl Number {
+    pub fn from_big(n: BigInt) -> Number {
+        match n.to_i64() {
+            Some(i) => Number::Int(i),
+            None => Number::Big(Rc::new(n)),
+        }
+    }
+
+    pub fn from_ratio(r: BigRational) -> Number {
+        if r.is_integer() {
+            Number::from_big(r.to_integer())
+        } else {
+            Number::Ratio(Rc::new(r))
+        }
+    }
+
+    /// Parses an integer, a `numerator/denominator` ratio or a float,
+    /// including `+inf.0`, `-inf.0` and `+nan.0` as they are printed.
+    pub fn parse(token: &str) -> Option<Number> {
+        match token {
+            "+inf.0" => return Some(Number::Float(f64::INFINITY)),
+            "-inf.0" => return Some(Number::Float(f64::NEG_INFINITY)),
+            "+nan.0" | "-nan.0" => return Some(Number::Float(f64::NAN)),
+            _ => {}
+        }
+        if let Ok(i) = token.parse::<i64>() {
+            return Some(Number::Int(i));
+        }
+        if let Ok(n) = token.parse::<BigInt>() {
+            return Some(Number::from_big(n));
+        }
+        if let Some((numer, denom)) = token.split_once('/') {
+            let numer = numer.parse::<BigInt>().ok()?;
+            let denom = denom.parse::<BigInt>().ok().filter(|d| d.is_positive())?;
+            return Some(Number::from_ratio(BigRational::new(numer, denom)));
+        }
+        // Rust also accepts `inf` and `nan`, which should stay symbols.
+        if token.chars().any(|c| c.is_ascii_digit()) {
+            return token.parse::<f64>().ok().map(Number::Float);
+        }
+        None
+    }
+
+    fn level(&self) -> Level {
+        match self {
+            Number::Int(_) | Number::Big(_) => Level::Integer,
+            Number::Ratio(_) => Level::Ratio,
+            Number::Float(_) => Level::Float,
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.level() != Level::Float
+    }
+
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Number::Int(_) | Number::Big(_) => true,
+            Number::Ratio(_) => false,
+            Number::Float(f) => f.fract() == 0.0,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Number::Int(i) => *i == 0,
+            Number::Big(_) | Number::Ratio(_) => false,
+            Number::Float(f) => *f == 0.0,
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Number::Int(i) => *i as f64,
+            Number::Big(n) => n.to_f64().unwrap_or(f64::NAN),
+            Number::Ratio(r) => r.to_f64().unwrap_or(f64::NAN),
+            Number::Float(f) => *f,
+        }
+    }
+
+    /// Only meaningful for exact integers.
+    fn to_big(&self) -> BigInt {
+        match self {
+            Number::Int(i) => BigInt::from(*i),
+            Number::Big(n) => n.as_ref().clone(),
+            Number::Ratio(r) => r.to_integer(),
+            Number::Float(f) => BigInt::from_f64(*f).unwrap_or_default(),
+        }
+    }
+
+    /// Only meaningful for exact numbers.
+    fn to_ratio(&self) -> BigRational {
+        match self {
+            Number::Ratio(r) => r.as_ref().clone(),
+            _ => BigRational::from_integer(self.to_big()),
+        }
+    }
+
+    /// The value as an index, if it is an exact non-negative integer.
+    pub fn to_index(&self) -> Option<usize> {
+        match self {
+            Number::Int(i) => usize::try_from(*i).ok(),
+            _ => None,
+        }
+    }
+
+    fn binary(
+        &self,
+        other: &Number,
+        int: fn(i64, i64) -> Option<i64>,
+        big: fn(BigInt, BigInt) -> BigInt,
+        ratio: fn(BigRational, BigRational) -> BigRational,
+        float: fn(f64, f64) -> f64,
+    ) -> Number {
+        match self.level().max(other.level()) {
+            Level::Float => Number::Float(float(self.to_f64(), other.to_f64())),
+            Level::Ratio => Number::from_ratio(ratio(self.to_ratio(), other.to_ratio())),
+            Level::Integer => match (self, other) {
+                (Number::Int(a), Number::Int(b)) => match int(*a, *b) {
+                    Some(i) => Number::Int(i),
+                    None => Number::from_big(big(self.to_big(), other.to_big())),
+                },
+                _ => Number::from_big(big(self.to_big(), other.to_big())),
+            },
+        }
+    }
+
+    /// Exact division yields a ratio unless the result is integral.
+    pub fn checked_div(&self, other: &Number) -> Result<Number, RispErr> {
+        if other.is_exact() && other.is_zero() {
+            return Err(RispErr::Arithmetic("division by zero".to_string()));
+        }
+        Ok(match self.level().max(other.level()) {
+            Level::Float => Number::Float(self.to_f64() / other.to_f64()),
+            _ => Number::from_ratio(self.to_ratio() / other.to_ratio()),
+        })
+    }
+
+    /// Applies an integer division operator. Integral floats are accepted and
+    /// give an inexact result.
+    fn integer_division(
+        &self,
+        other: &Number,
+        name: &str,
+        big: fn(&BigInt, &BigInt) -> BigInt,
+        float: fn(f64, f64) -> f64,
+    ) -> Result<Number, RispErr> {
+        if !self.is_integer() || !other.is_integer() {
+            return Err(RispErr::Type(format!("{name}: expected integers")));
+        }
+        if other.is_zero() {
+            return Err(RispErr::Arithmetic("division by zero".to_string()));
+        }
+        Ok(match self.level().max(other.level()) {
+            Level::Float => Number::Float(float(self.to_f64(), other.to_f64())),
+            _ => Number::from_big(big(&self.to_big(), &other.to_big())),
+        })
+    }
+
+    /// Division rounded towards zero.
+    pub fn quotient(&self, other: &Number) -> Result<Number, RispErr> {
+        self.integer_division(other, "quotient", |a, b| a / b, |a, b| (a / b).trunc())
+    }
+
+    /// Remainder with the sign of the dividend.
+    pub fn remainder(&self, other: &Number) -> Result<Number, RispErr> {
+        self.integer_division(other, "remainder", |a, b| a % b, |a, b| a % b)
+    }
+
+    /// Remainder with the sign of the divisor.
+    pub fn modulo(&self, other: &Number) -> Result<Number, RispErr> {
+        self.integer_division(other, "modulo", Integer::mod_floor, |a, b| {
+            a - b * (a / b).floor()
+        })
+    }
+
+    pub fn floor(&self) -> Number {
+        match self {
+            Number::Ratio(r) => Number::from_big(r.floor().to_integer()),
+            Number::Float(f) => Number::Float(f.floor()),
+            _ => self.clone(),
+        }
+    }
+
+    /// Rounds to the nearest integer, and to the even one on ties.
+    pub fn round(&self) -> Number {
+        match self {
+            Number::Ratio(r) => {
+                let floor = r.floor();
+                let diff = r.as_ref() - &floor;
+                let half = BigRational::new(BigInt::one(), BigInt::from(2));
+                let floor = floor.to_integer();
+                let rounded = match diff.cmp(&half) {
+                    Ordering::Less => floor,
+                    Ordering::Greater => floor + 1,
+                    Ordering::Equal if floor.is_even() => floor,
+                    Ordering::Equal => floor + 1,
+                };
+                Number::from_big(rounded)
+            }
+            Number::Float(f) => Number::Float(f.round_ties_even()),
+            _ => self.clone(),
+        }
+    }
+
+    pub fn to_inexact(&self) -> Number {
+        Number::Float(self.to_f64())
+    }
+
+    /// Identity as seen by `eq?`: numbers stored inline compare by value,
+    /// heap-allocated ones by pointer.
+    pub fn is_identical(&self, other: &Number) -> bool {
+        match (self, other) {
+            (Number::Int(a), Number::Int(b)) => a == b,
+            (Number::Big(a), Number::Big(b)) => Rc::ptr_eq(a, b),
+            (Number::Ratio(a), Number::Ratio(b)) => Rc::ptr_eq(a, b),
+            (Number::Float(a), Number::Float(b)) => a.to_bits() == b.to_bits(),
+            _ => false,
+        }
+    }
+
+    /// Compares numerically, so `1`, `1.0` and `2/2` are all equal. Returns
+    /// `None` if either side is NaN.
+    pub fn num_cmp(&self, other: &Number) -> Option<Ordering> {
+        match self.level().max(other.level()) {
+            Level::Float => self.to_f64().partial_cmp(&other.to_f64()),
+            Level::Ratio => Some(self.to_ratio().cmp(&other.to_ratio())),
+            Level::Integer => match (self, other) {
+                (Number::Int(a), Number::Int(b)) => Some(a.cmp(b)),
+                _ => Some(self.to_big().cmp(&other.to_big())),
+            },
+        }
+    }
+}
+
+/// Structural equality, as used for map keys and set members: `1` and
+/// `1.0` are different values here even though `(= 1 1.0)` holds.
+impl PartialEq for Number {
+    fn eq(&self, other: &Number) -> bool {
+        match (self, other) {
+            (Number::Int(a), Number::Int(b)) => a == b,
+            (Number::Big(a), Number::Big(b)) => a == b,
+            (Number::Ratio(a), Number::Ratio(b)) => a == b,
+            (Number::Float(a), Number::Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Number {}
+
+impl Hash for Number {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Number::Int(i) => i.hash(state),
+            Number::Big(n) => n.hash(state),
+            Number::Ratio(r) => r.hash(state),
+            // 0.0 and -0.0 are equal, as are all NaNs.
+            Number::Float(f) if *f == 0.0 => 0.0f64.to_bits().hash(state),
+            Number::Float(f) if f.is_nan() => f64::NAN.to_bits().hash(state),
+            Number::Float(f) => f.to_bits().hash(state),
+        }
+    }
+}
+
+impl Add for Number {
+    type Output = Number;
+    fn add(self, other: Number) -> Number {
+        self.binary(
+            &other,
+            i64::checked_add,
+            |a, b| a + b,
+            |a, b| a + b,
+            |a, b| a + b,
+        )
+    }
+}
+
+impl Sub for Number {
+    type Output = Number;
+    fn sub(self, other: Number) -> Number {
+        self.binary(
+            &other,
+            i64::checked_sub,
+            |a, b| a - b,
+            |a, b| a - b,
+            |a, b| a - b,
+        )
+    }
+}
+
+impl Mul for Number {
+    type Output = Number;
+    fn mul(self, other: Number) -> Number {
+        self.binary(
+            &other,
+            i64::checked_mul,
+            |a, b| a * b,
+            |a, b| a * b,
+            |a, b| a * b,
+        )
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Number::Int(i) => write!(f, "{i}"),
+            Number::Big(n) => write!(f, "{n}"),
+            Number::Ratio(r) => write!(f, "{}/{}", r.numer(), r.denom()),
+            // Keep a decimal point on integral floats so they read back as
+            // inexact numbers.
+            Number::Float(x) if x.is_finite() && x.fract() == 0.0 => write!(f, "{x:.1}"),
+            Number::Float(x) if x.is_nan() => write!(f, "+nan.0"),
+            Number::Float(x) if x.is_infinite() => {
+                write!(f, "{}inf.0", if *x > 0.0 { '+' } else { '-' })
+            }
+            Number::Float(x) => write!(f, "{x}"),
+        }
+    }
+}
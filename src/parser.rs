@@ -1,72 +1,182 @@
+use crate::atom::Keyword;
+use crate::collections;
+use crate::errors;
 use crate::eval::*;
+use crate::lexer::*;
+use crate::list::RispList;
+use crate::lists;
+use crate::number::*;
+use crate::pattern::Pattern;
+use crate::port;
+use crate::pretty;
+use crate::printer::display_string;
+use crate::regexes;
 use crate::risp_type::*;
+use crate::span::*;
+use crate::strings;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::rc::Rc;
-use std::{collections::HashMap, num::ParseFloatError};
+use std::sync::atomic::{self, AtomicUsize};
 
-pub fn tokenize(expr: String) -> Vec<String> {
-    expr.replace("#'(", " ( fn ")
-        .replace("'(", " ( list ")
-        .replace("(", " ( ")
-        .replace(")", " ) ")
-        .split_whitespace()
-        .map(|x| x.to_string())
-        .collect()
+/// Drops `#_` markers along with the datum each of them discards.
+pub fn skip_discarded(tokens: &[Token]) -> Result<&[Token], RispErr> {
+    let mut xs = tokens;
+    while let Some((token, rest)) = xs.split_first() {
+        if token.kind != TokenKind::Discard {
+            break;
+        }
+        let (_, rest) = parse(rest)?;
+        xs = rest;
+    }
+    Ok(xs)
 }
 
-pub fn parse(tokens: &[String]) -> Result<(RispExp, &[String]), RispErr> {
-    let (token, rest) = tokens
+pub fn parse(tokens: &[Token]) -> Result<(RispExp, &[Token]), RispErr> {
+    let (token, rest) = skip_discarded(tokens)?
         .split_first()
         .ok_or(RispErr::Reason("could not get token".to_string()))?;
 
-    match &token[..] {
-        "(" => read_seq(rest),
-        ")" => Err(RispErr::Reason("unexpected `)`".to_string())),
-        _ => Ok((parse_atom(token), rest)),
+    match &token.kind {
+        TokenKind::LParen => read_seq(rest, &token.span),
+        TokenKind::LBrace => read_map(rest, &token.span),
+        TokenKind::HashBrace => read_set(rest, &token.span),
+        TokenKind::LBracket => read_vector(rest, &token.span),
+        TokenKind::RParen => {
+            Err(RispErr::Reason("unexpected `)`".to_string()).at(token.span.clone()))
+        }
+        TokenKind::RBracket => {
+            Err(RispErr::Reason("unexpected `]`".to_string()).at(token.span.clone()))
+        }
+        TokenKind::RBrace => {
+            Err(RispErr::Reason("unexpected `}`".to_string()).at(token.span.clone()))
+        }
+        TokenKind::Str(s) => Ok((RispExp::Literal(s.clone()), rest)),
+        TokenKind::Char(c) => Ok((RispExp::Char(*c), rest)),
+        TokenKind::Regex(source) => match Pattern::new(source) {
+            Ok(pattern) => Ok((RispExp::Pattern(pattern), rest)),
+            Err(err) => Err(err.at(token.span.clone())),
+        },
+        TokenKind::Atom(atom) => Ok((parse_atom(atom, &token.span), rest)),
+        TokenKind::Quote => read_quoted("quote", rest, &token.span),
+        TokenKind::Quasiquote => read_quoted("quasiquote", rest, &token.span),
+        TokenKind::Unquote => read_quoted("unquote", rest, &token.span),
+        TokenKind::UnquoteSplicing => read_quoted("unquote-splicing", rest, &token.span),
+        TokenKind::Discard => unreachable!("discarded by skip_discarded"),
+    }
+}
+
+/// Expands a reader macro such as `'x` into `(quote x)`.
+fn read_quoted<'a>(
+    head: &str,
+    tokens: &'a [Token],
+    prefix: &Span,
+) -> Result<(RispExp, &'a [Token]), RispErr> {
+    if skip_discarded(tokens)?.is_empty() {
+        return Err(RispErr::Reason(format!("expected a form after {head}")).at(prefix.clone()));
     }
+    let (exp, rest) = parse(tokens)?;
+    // The form spans the prefix and the whole of the datum after it.
+    let last = &tokens[tokens.len() - rest.len() - 1];
+    let symbol = RispExp::Symbol(head.to_string(), Some(prefix.clone()));
+    Ok((
+        RispExp::List(vec![symbol, exp].into(), Some(prefix.to(&last.span))),
+        rest,
+    ))
 }
 
-fn read_seq(tokens: &[String]) -> Result<(RispExp, &[String]), RispErr> {
+/// Reads forms up to the `close` token, returning them along with the span
+/// from `open` to the closing token.
+fn read_items<'a>(
+    tokens: &'a [Token],
+    open: &Span,
+    close: TokenKind,
+) -> Result<(Vec<RispExp>, Span, &'a [Token]), RispErr> {
+    let closing = match close {
+        TokenKind::RParen => ")",
+        TokenKind::RBracket => "]",
+        _ => "}",
+    };
     let mut res: Vec<RispExp> = vec![];
     let mut xs = tokens;
     loop {
-        let (next_token, rest) = xs
-            .split_first()
-            .ok_or(RispErr::Reason("could not find closing `)`".to_string()))?;
-        if next_token == ")" {
-            return Ok((RispExp::List(res), rest));
+        xs = skip_discarded(xs)?;
+        let (next_token, rest) = xs.split_first().ok_or_else(|| {
+            RispErr::Reason(format!("could not find closing `{closing}`")).at(open.clone())
+        })?;
+        if next_token.kind == close {
+            return Ok((res, open.to(&next_token.span), rest));
         }
-        let (exp, new_xs) = parse(&xs)?;
+        let (exp, new_xs) = parse(xs)?;
         res.push(exp);
         xs = new_xs;
     }
 }
 
-fn parse_atom(token: &str) -> RispExp {
-    match token.as_ref() {
+fn read_seq<'a>(tokens: &'a [Token], open: &Span) -> Result<(RispExp, &'a [Token]), RispErr> {
+    let (items, span, rest) = read_items(tokens, open, TokenKind::RParen)?;
+    Ok((RispExp::List(items.into(), Some(span)), rest))
+}
+
+fn read_vector<'a>(tokens: &'a [Token], open: &Span) -> Result<(RispExp, &'a [Token]), RispErr> {
+    let (items, _, rest) = read_items(tokens, open, TokenKind::RBracket)?;
+    Ok((RispExp::Vector(items.into()), rest))
+}
+
+fn read_map<'a>(tokens: &'a [Token], open: &Span) -> Result<(RispExp, &'a [Token]), RispErr> {
+    let (items, span, rest) = read_items(tokens, open, TokenKind::RBrace)?;
+    if !items.len().is_multiple_of(2) {
+        return Err(RispErr::Reason(
+            "map literal must contain an even number of forms".to_string(),
+        )
+        .at(span));
+    }
+    let mut map = im_rc::HashMap::new();
+    for pair in items.chunks(2) {
+        if map.insert(pair[0].clone(), pair[1].clone()).is_some() {
+            return Err(
+                RispErr::Reason(format!("duplicate key '{}' in map literal", pair[0])).at(span),
+            );
+        }
+    }
+    let forms = LiteralForms { forms: items, span };
+    Ok((RispExp::Map(map, Some(Rc::new(forms))), rest))
+}
+
+fn read_set<'a>(tokens: &'a [Token], open: &Span) -> Result<(RispExp, &'a [Token]), RispErr> {
+    let (items, span, rest) = read_items(tokens, open, TokenKind::RBrace)?;
+    let mut set = im_rc::HashSet::new();
+    for item in &items {
+        if set.insert(item.clone()).is_some() {
+            return Err(
+                RispErr::Reason(format!("duplicate element '{item}' in set literal")).at(span),
+            );
+        }
+    }
+    let forms = LiteralForms { forms: items, span };
+    Ok((RispExp::Set(set, Some(Rc::new(forms))), rest))
+}
+
+fn parse_atom(token: &str, span: &Span) -> RispExp {
+    match token {
         "true" => RispExp::Bool(true),
         "false" => RispExp::Bool(false),
         "nil" => RispExp::Nil,
-        _ => {
-            let potential_float: Result<f64, ParseFloatError> = token.parse();
-            match potential_float {
-                Ok(v) => RispExp::Number(v),
-                Err(_) => {
-                    if token.starts_with("\"") && token.ends_with("\"") {
-                        RispExp::Literal(token[1..token.len() - 1].to_string())
-                    } else {
-                        RispExp::Symbol(token.to_string().clone())
-                    }
-                }
-            }
+        _ if token.len() > 1 && token.starts_with(':') => {
+            RispExp::Keyword(Keyword::new(&token[1..]))
         }
+        _ => match Number::parse(token) {
+            Some(n) => RispExp::Number(n),
+            None => RispExp::Symbol(token.to_string(), Some(span.clone())),
+        },
     }
 }
 
-fn parse_single_list(exp: &RispExp) -> Result<Vec<RispExp>, RispErr> {
+fn parse_single_list(exp: &RispExp) -> Result<RispList, RispErr> {
     match exp {
-        RispExp::List(list) => Ok(list.to_vec()),
-        _ => Err(RispErr::Reason("this value is not list".to_string())),
+        RispExp::List(list, _) => Ok(list.clone()),
+        _ => Err(RispErr::Type("this value is not list".to_string())),
     }
 }
 
@@ -74,161 +184,307 @@ fn parse_single_list(exp: &RispExp) -> Result<Vec<RispExp>, RispErr> {
 //    Ok(args.to_vec())
 //}
 
-fn parse_list_of_floats(args: &[RispExp]) -> Result<Vec<f64>, RispErr> {
-    args.iter().map(|x| parse_single_float(x)).collect()
+fn parse_list_of_numbers(args: &[RispExp]) -> Result<Vec<Number>, RispErr> {
+    args.iter().map(parse_single_number).collect()
 }
 
-fn parse_single_float(exp: &RispExp) -> Result<f64, RispErr> {
+fn parse_single_number(exp: &RispExp) -> Result<Number, RispErr> {
     match exp {
-        RispExp::Number(num) => Ok(*num),
-        _ => Err(RispErr::Reason("expected number".to_string())),
+        RispExp::Number(num) => Ok(num.clone()),
+        _ => Err(RispErr::Type("expected number".to_string())),
     }
 }
 
 pub fn parse_list_of_symbol_strings(list: Rc<RispExp>) -> Result<Vec<String>, RispErr> {
-    let evaled_list = match list.as_ref() {
-        RispExp::List(l) => Ok(l.clone()),
+    let name = |x: &RispExp| match x {
+        RispExp::Symbol(s, _) => Ok(s.clone()),
+        _ => Err(RispErr::Reason(
+            "expected symbols in the argument list".to_string(),
+        )),
+    };
+    match list.as_ref() {
+        RispExp::List(l, _) => l.iter().map(name).collect(),
+        RispExp::Vector(v) => v.iter().map(name).collect(),
         _ => Err(RispErr::Reason(
             "expected args form to be a list".to_string(),
         )),
-    }?;
-    evaled_list
-        .iter()
-        .map(|x| match x {
-            RispExp::Symbol(s) => Ok(s.clone()),
-            _ => Err(RispErr::Reason(
-                "expected symbols in the argument list".to_string(),
-            )),
-        })
-        .collect()
+    }
 }
 
 macro_rules! inequality_sign {
-    ($check_fn:expr) => {{
-        |args: &[RispExp]| -> RispResult {
-            let floats = parse_list_of_floats(args)?;
-            let first = floats
-                .first()
-                .ok_or(RispErr::Reason("expected at least one number".to_string()))?;
-            let rest = &floats[1..];
-            fn f(prev: &f64, xs: &[f64]) -> bool {
+    ($name:expr, $check_fn:expr) => {{
+        RispFunc::new($name, Arity::AtLeast(1), |args, _| {
+            let numbers = parse_list_of_numbers(args)?;
+            let (first, rest) = numbers.split_first().expect("arity is checked by call");
+            fn f(prev: &Number, xs: &[Number]) -> bool {
                 match xs.first() {
                     Some(x) => $check_fn(prev, x) && f(x, &xs[1..]),
                     None => true,
                 }
             }
             Ok(RispExp::Bool(f(first, rest)))
-        }
+        })
+    }};
+}
+
+macro_rules! equality_predicate {
+    ($name:expr, $check_fn:expr) => {{
+        RispFunc::fixed($name, |[a, b], _| Ok(RispExp::Bool($check_fn(a, b))))
     }};
 }
 
 macro_rules! arithmetic_operation {
-    ($math_fn:expr) => {{
-        |args: &[RispExp]| -> RispResult {
-            let floats = parse_list_of_floats(args)?;
-            let result = floats
+    ($name:expr, $math_fn:expr) => {{
+        RispFunc::new($name, Arity::AtLeast(1), |args, _| {
+            let numbers = parse_list_of_numbers(args)?;
+            let result = numbers
                 .into_iter()
                 .reduce($math_fn)
-                .ok_or(RispErr::Reason("expected empty list".to_string()))?;
+                .expect("arity is checked by call");
             Ok(RispExp::Number(result))
-        }
+        })
+    }};
+}
+
+macro_rules! unary_operation {
+    ($name:expr, $math_fn:expr) => {{
+        RispFunc::fixed($name, |[n], _| {
+            Ok(RispExp::Number($math_fn(&parse_single_number(n)?)))
+        })
+    }};
+}
+
+macro_rules! integer_operation {
+    ($name:expr, $math_fn:expr) => {{
+        RispFunc::fixed($name, |[a, b], _| {
+            Ok(RispExp::Number($math_fn(
+                &parse_single_number(a)?,
+                &parse_single_number(b)?,
+            )?))
+        })
     }};
 }
 
-pub fn standard_env<'a>() -> RispEnv<'a> {
+pub fn standard_env() -> RispEnv {
     let mut data: HashMap<String, RispExp> = HashMap::new();
-    data.insert(
-        "+".to_string(),
-        RispExp::Func(arithmetic_operation!(|a, b| a + b)),
-    );
-    data.insert(
-        "-".to_string(),
-        RispExp::Func(arithmetic_operation!(|a, b| a - b)),
-    );
-    data.insert(
-        "*".to_string(),
-        RispExp::Func(arithmetic_operation!(|a, b| a * b)),
-    );
-    data.insert(
-        "/".to_string(),
-        RispExp::Func(|args: &[RispExp]| -> RispResult {
-            let floats = parse_list_of_floats(args)?;
-            let first = *floats
-                .first()
-                .ok_or(RispErr::Reason("expected at least one number".to_string()))?;
-            if first == 0.0 {
-                return Err(RispErr::Reason("'/' first element can't 0".to_string()));
+    insert_func(&mut data, arithmetic_operation!("+", |a, b| a + b));
+    insert_func(
+        &mut data,
+        RispFunc::new("-", Arity::AtLeast(1), |args, _| {
+            let numbers = parse_list_of_numbers(args)?;
+            // `(- x)` negates `x`.
+            let result = match numbers.as_slice() {
+                [x] => Number::Int(0) - x.clone(),
+                _ => numbers
+                    .into_iter()
+                    .reduce(|a, b| a - b)
+                    .expect("arity is checked by call"),
+            };
+            Ok(RispExp::Number(result))
+        }),
+    );
+    insert_func(&mut data, arithmetic_operation!("*", |a, b| a * b));
+    insert_func(
+        &mut data,
+        RispFunc::new("/", Arity::AtLeast(1), |args, _| {
+            let numbers = parse_list_of_numbers(args)?;
+            let (first, rest) = numbers.split_first().expect("arity is checked by call");
+            // `(/ x)` is the reciprocal of `x`.
+            if rest.is_empty() {
+                return Ok(RispExp::Number(Number::Int(1).checked_div(first)?));
+            }
+            let mut result = first.clone();
+            for n in rest {
+                result = result.checked_div(n)?;
             }
-            Ok(RispExp::Number(
-                floats[1..].iter().fold(first, |a, b| a / b),
+            Ok(RispExp::Number(result))
+        }),
+    );
+    insert_func(&mut data, integer_operation!("quotient", Number::quotient));
+    insert_func(
+        &mut data,
+        integer_operation!("remainder", Number::remainder),
+    );
+    insert_func(&mut data, integer_operation!("modulo", Number::modulo));
+    insert_func(&mut data, unary_operation!("floor", Number::floor));
+    insert_func(&mut data, unary_operation!("round", Number::round));
+    insert_func(
+        &mut data,
+        unary_operation!("exact->inexact", Number::to_inexact),
+    );
+    insert_func(
+        &mut data,
+        RispFunc::new("=", Arity::AtLeast(1), |args, _| {
+            Ok(RispExp::Bool(args.windows(2).all(|w| w[0].is_equal(&w[1]))))
+        }),
+    );
+    insert_func(
+        &mut data,
+        RispFunc::new("not=", Arity::AtLeast(1), |args, _| {
+            Ok(RispExp::Bool(
+                !args.windows(2).all(|w| w[0].is_equal(&w[1])),
             ))
         }),
     );
-    data.insert(
-        "=".to_string(),
-        RispExp::Func(inequality_sign!(|a, b| a == b)),
-    );
-    data.insert(
-        ">".to_string(),
-        RispExp::Func(inequality_sign!(|a, b| a > b)),
-    );
-    data.insert(
-        "<".to_string(),
-        RispExp::Func(inequality_sign!(|a, b| a < b)),
-    );
-    data.insert(
-        ">=".to_string(),
-        RispExp::Func(inequality_sign!(|a, b| a >= b)),
-    );
-    data.insert(
-        "<=".to_string(),
-        RispExp::Func(inequality_sign!(|a, b| a <= b)),
-    );
-    data.insert(
-        "list".to_string(),
-        RispExp::Func(|args: &[RispExp]| -> RispResult { Ok(RispExp::List(args.to_vec())) }),
-    );
-    data.insert(
-        "car".to_string(),
-        RispExp::Func(|args: &[RispExp]| -> RispResult {
-            let [list_exp] = <&[RispExp; 1]>::try_from(args).ok().ok_or(RispErr::Reason(
-                "Wrong number of arguments: car, 2".to_string(),
-            ))?;
-            Ok(native_car(parse_single_list(list_exp)?.as_ref())?)
+    insert_func(&mut data, equality_predicate!("eq?", RispExp::is_identical));
+    insert_func(&mut data, equality_predicate!("eqv?", RispExp::is_eqv));
+    insert_func(&mut data, equality_predicate!("equal?", RispExp::eq));
+    insert_func(
+        &mut data,
+        inequality_sign!(">", |a: &Number, b| a.num_cmp(b) == Some(Ordering::Greater)),
+    );
+    insert_func(
+        &mut data,
+        inequality_sign!("<", |a: &Number, b| a.num_cmp(b) == Some(Ordering::Less)),
+    );
+    insert_func(
+        &mut data,
+        inequality_sign!(">=", |a: &Number, b| matches!(
+            a.num_cmp(b),
+            Some(Ordering::Greater | Ordering::Equal)
+        )),
+    );
+    insert_func(
+        &mut data,
+        inequality_sign!("<=", |a: &Number, b| matches!(
+            a.num_cmp(b),
+            Some(Ordering::Less | Ordering::Equal)
+        )),
+    );
+    insert_func(
+        &mut data,
+        RispFunc::new("list", Arity::AtLeast(0), |args, _| {
+            Ok(RispExp::List(args.iter().cloned().collect(), None))
+        }),
+    );
+    insert_func(
+        &mut data,
+        RispFunc::fixed("car", |[list_exp], _| {
+            native_car(&parse_single_list(list_exp)?)
+        }),
+    );
+    insert_func(
+        &mut data,
+        RispFunc::fixed("cdr", |[list_exp], _| {
+            native_cdr(&parse_single_list(list_exp)?)
         }),
     );
-    data.insert(
-        "cdr".to_string(),
-        RispExp::Func(|args: &[RispExp]| -> RispResult {
-            let [list_exp] = <&[RispExp; 1]>::try_from(args).ok().ok_or(RispErr::Reason(
-                "Wrong number of arguments: cdr, 2".to_string(),
-            ))?;
-            Ok(native_cdr(parse_single_list(list_exp)?.as_ref())?)
+    insert_func(
+        &mut data,
+        RispFunc::fixed("nth", |[idx_exp, list_exp], _| {
+            let idx = parse_index(idx_exp, "nth")?;
+            let item = match list_exp {
+                RispExp::Vector(v) => v.get(idx).cloned(),
+                _ => parse_single_list(list_exp)?.get(idx).cloned(),
+            };
+            Ok(item.unwrap_or(RispExp::Nil))
         }),
     );
-    data.insert(
-        "nth".to_string(),
-        RispExp::Func(|args: &[RispExp]| -> RispResult {
-            let [idx_exp, list_exp] = <&[RispExp; 2]>::try_from(args).ok().ok_or(
-                RispErr::Reason("Wrong number of arguments: nth, 3".to_string()),
-            )?;
-            let list = parse_single_list(list_exp)?;
-            let idx = parse_single_float(idx_exp)? as usize;
-            Ok(list.into_iter().nth(idx).unwrap_or(RispExp::Nil))
+    insert_func(
+        &mut data,
+        RispFunc::new("gensym", Arity::Between(0, 1), |args, _| {
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+            let prefix = match args.first() {
+                Some(prefix) => display_string(prefix),
+                None => "G__".to_string(),
+            };
+            let id = COUNTER.fetch_add(1, atomic::Ordering::Relaxed);
+            Ok(RispExp::Symbol(format!("{prefix}{id}"), None))
         }),
     );
-    data.insert(
-        "range".to_string(),
-        RispExp::Func(|args: &[RispExp]| -> RispResult {
-            if args.len() == 1 {
-                let idx = parse_single_float(&args[0])? as usize;
-            } else if args.len() == 2 {
-                let idx = parse_single_float(&args[1])? as usize;
+    insert_func(
+        &mut data,
+        RispFunc::fixed("keyword", |[name], _| match name {
+            RispExp::Keyword(_) => Ok(name.clone()),
+            // `:` alone reads as a symbol, so there is no empty keyword.
+            RispExp::Symbol(s, _) | RispExp::Literal(s) if s.is_empty() => Err(RispErr::Reason(
+                "keyword: the name must not be empty".to_string(),
+            )),
+            RispExp::Symbol(s, _) | RispExp::Literal(s) => Ok(RispExp::Keyword(Keyword::new(s))),
+            _ => Err(RispErr::Type(format!(
+                "keyword: expected a string or symbol, got '{name}'"
+            ))),
+        }),
+    );
+    insert_func(
+        &mut data,
+        RispFunc::fixed("name", |[exp], _| match exp {
+            RispExp::Keyword(k) => Ok(RispExp::Literal(k.name().to_string())),
+            RispExp::Symbol(s, _) | RispExp::Literal(s) => Ok(RispExp::Literal(s.clone())),
+            _ => Err(RispErr::Type(format!(
+                "name: expected a keyword, symbol or string, got '{exp}'"
+            ))),
+        }),
+    );
+    insert_func(
+        &mut data,
+        RispFunc::fixed("char->integer", |[exp], _| match exp {
+            RispExp::Char(c) => Ok(RispExp::Number(Number::Int(*c as i64))),
+            _ => Err(RispErr::Type(format!(
+                "char->integer: expected a character, got '{exp}'"
+            ))),
+        }),
+    );
+    insert_func(
+        &mut data,
+        RispFunc::fixed("integer->char", |[code], _| {
+            parse_single_number(code)?
+                .to_index()
+                .and_then(|i| u32::try_from(i).ok())
+                .and_then(char::from_u32)
+                .map(RispExp::Char)
+                .ok_or(RispErr::Reason(format!(
+                    "integer->char: '{code}' is not a unicode scalar value"
+                )))
+        }),
+    );
+    insert_func(
+        &mut data,
+        RispFunc::new("range", Arity::Between(1, 3), |args, _| {
+            let (start, end, step) = match args {
+                [end] => (Number::Int(0), parse_single_number(end)?, Number::Int(1)),
+                [start, end] => (
+                    parse_single_number(start)?,
+                    parse_single_number(end)?,
+                    Number::Int(1),
+                ),
+                [start, end, step] => (
+                    parse_single_number(start)?,
+                    parse_single_number(end)?,
+                    parse_single_number(step)?,
+                ),
+                _ => unreachable!("arity is checked by call"),
+            };
+            // A negative step counts down towards `end`.
+            let towards_end = match step.num_cmp(&Number::Int(0)) {
+                Some(Ordering::Greater) => Ordering::Less,
+                Some(Ordering::Less) => Ordering::Greater,
+                _ => return Err(RispErr::Reason("range: step must not be zero".to_string())),
+            };
+            let mut list = vec![];
+            let mut n = start;
+            while n.num_cmp(&end) == Some(towards_end) {
+                list.push(RispExp::Number(n.clone()));
+                let next = n.clone() + step.clone();
+                // A float step can be too small to change a large float.
+                if next.num_cmp(&n) == Some(Ordering::Equal) {
+                    return Err(RispErr::Reason(format!(
+                        "range: step {step} does not advance past {n}"
+                    )));
+                }
+                n = next;
             }
-            let idx = parse_single_float(idx_exp)? as usize;
-            Ok(list.into_iter().nth(idx).unwrap_or(RispExp::Nil))
+            Ok(RispExp::List(list.into(), None))
         }),
     );
 
-    RispEnv { data, outer: None }
+    collections::insert_builtins(&mut data);
+    lists::insert_builtins(&mut data);
+    errors::insert_builtins(&mut data);
+    strings::insert_builtins(&mut data);
+    regexes::insert_builtins(&mut data);
+    pretty::insert_builtins(&mut data);
+    port::insert_builtins(&mut data);
+
+    RispEnv::new(data)
 }
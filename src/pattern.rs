@@ -0,0 +1,108 @@
+use crate::risp_type::RispErr;
+use core::fmt::{self, Write};
+use regex::Regex;
+use std::cell::OnceCell;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+/// A compiled regular expression, read from a `#"..."` literal or made by
+/// `re-pattern`. Patterns are compiled once and shared by clones, and
+/// compare by their source text.
+#[derive(Clone)]
+pub struct Pattern(Rc<Compiled>);
+
+struct Compiled {
+    regex: Regex,
+    /// The pattern anchored at both ends, for `re-matches`.
+    anchored: OnceCell<Regex>,
+}
+
+impl Pattern {
+    /// Compiles `source`. A `\"` in it stands for `"`, as it does in a
+    /// `#"..."` literal, so that every pattern prints as a literal that
+    /// reads back as the same source.
+    pub fn new(source: &str) -> Result<Pattern, RispErr> {
+        let regex = Regex::new(&unescape_quotes(source))
+            .map_err(|e| RispErr::Reason(format!("invalid regular expression: {e}")))?;
+        Ok(Pattern(Rc::new(Compiled {
+            regex,
+            anchored: OnceCell::new(),
+        })))
+    }
+
+    pub fn regex(&self) -> &Regex {
+        &self.0.regex
+    }
+
+    /// A regex that only matches the whole of a string, compiled the first
+    /// time it is needed.
+    pub fn anchored(&self) -> &Regex {
+        self.0.anchored.get_or_init(|| {
+            Regex::new(&format!("^(?:{})$", self.as_str())).expect("the pattern already compiled")
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.regex.as_str()
+    }
+
+    pub fn ptr_eq(&self, other: &Pattern) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Pattern) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Pattern {}
+
+impl Hash for Pattern {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+/// Replaces each `\"` with `"`, leaving other escapes such as `\\` alone.
+fn unescape_quotes(source: &str) -> String {
+    let mut res = String::with_capacity(source.len());
+    let mut chars = source.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('"') => res.push('"'),
+                next => {
+                    res.push('\\');
+                    res.extend(next);
+                }
+            },
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+/// Prints as the literal that reads back as the same pattern: the inverse of
+/// `Lexer::read_regex`, which keeps escapes as they are and reads `\"` as a
+/// quote. The source has no `\"` left to confuse the two.
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("#\"")?;
+        let mut chars = self.as_str().chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    f.write_char(c)?;
+                    if let Some(next) = chars.next() {
+                        f.write_char(next)?;
+                    }
+                }
+                '"' => f.write_str("\\\"")?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_str("\"")
+    }
+}
@@ -0,0 +1,287 @@
+//! Ports, which builtins such as `display` write to and `read-line` reads
+//! from. Output goes to the current output port, standard output unless
+//! redirected by `with-output-to-string` in Risp or `with_output` in Rust,
+//! so a host can capture what a script prints:
+//!
+//! ```
+//! use risp::port::{with_output, Port};
+//! use risp::Interpreter;
+//!
+//! let interp = Interpreter::new();
+//! let port = Port::output_string();
+//! with_output(port.clone(), || interp.eval_str("(display 1)")).unwrap();
+//! assert_eq!(port.contents().as_deref(), Some("1"));
+//! ```
+
+use crate::eval::apply;
+use crate::pretty::{pretty, PrettyOptions};
+use crate::printer::{join_printed, print, Mode};
+use crate::risp_type::*;
+use core::fmt;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+#[derive(Clone)]
+pub struct Port(Rc<PortKind>);
+
+enum PortKind {
+    Stdout,
+    Stdin,
+    OutputString(RefCell<String>),
+    /// The text and how far into it reading has got.
+    InputString(RefCell<(String, usize)>),
+}
+
+thread_local! {
+    static CURRENT_OUTPUT: RefCell<Port> = RefCell::new(Port::stdout());
+    static CURRENT_INPUT: RefCell<Port> = RefCell::new(Port::stdin());
+}
+
+impl Port {
+    pub fn stdout() -> Port {
+        Port(Rc::new(PortKind::Stdout))
+    }
+
+    pub fn stdin() -> Port {
+        Port(Rc::new(PortKind::Stdin))
+    }
+
+    /// A port that collects what is written to it, for `contents`.
+    pub fn output_string() -> Port {
+        Port(Rc::new(PortKind::OutputString(RefCell::new(String::new()))))
+    }
+
+    /// A port that reads from `text`.
+    pub fn input_string(text: impl Into<String>) -> Port {
+        Port(Rc::new(PortKind::InputString(RefCell::new((
+            text.into(),
+            0,
+        )))))
+    }
+
+    pub fn write_str(&self, s: &str) -> Result<(), RispErr> {
+        match &*self.0 {
+            PortKind::Stdout => {
+                // Flushed so that a prompt shows before a `read-line`.
+                let mut out = io::stdout();
+                out.write_all(s.as_bytes())?;
+                out.flush()?;
+            }
+            PortKind::OutputString(buf) => buf.borrow_mut().push_str(s),
+            _ => return Err(RispErr::Type(format!("{self} is not an output port"))),
+        }
+        Ok(())
+    }
+
+    /// The next line without its line ending, or `None` at the end of the
+    /// input.
+    pub fn read_line(&self) -> Result<Option<String>, RispErr> {
+        let mut line = match &*self.0 {
+            PortKind::Stdin => {
+                let mut line = String::new();
+                if io::stdin().lock().read_line(&mut line)? == 0 {
+                    return Ok(None);
+                }
+                line
+            }
+            PortKind::InputString(input) => {
+                let (text, pos) = &mut *input.borrow_mut();
+                let rest = &text[*pos..];
+                if rest.is_empty() {
+                    return Ok(None);
+                }
+                let len = rest.find('\n').map_or(rest.len(), |i| i + 1);
+                *pos += len;
+                rest[..len].to_string()
+            }
+            _ => return Err(RispErr::Type(format!("{self} is not an input port"))),
+        };
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+
+    /// What has been written to a string output port.
+    pub fn contents(&self) -> Option<String> {
+        match &*self.0 {
+            PortKind::OutputString(buf) => Some(buf.borrow().clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Ports compare by identity.
+impl PartialEq for Port {
+    fn eq(&self, other: &Port) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Port {}
+
+impl Hash for Port {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.0).hash(state);
+    }
+}
+
+impl fmt::Display for Port {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match &*self.0 {
+            PortKind::Stdout => "stdout",
+            PortKind::Stdin => "stdin",
+            PortKind::OutputString(_) => "string-output",
+            PortKind::InputString(_) => "string-input",
+        };
+        write!(f, "#<port {kind}>")
+    }
+}
+
+pub fn current_output() -> Port {
+    CURRENT_OUTPUT.with(|port| port.borrow().clone())
+}
+
+pub fn current_input() -> Port {
+    CURRENT_INPUT.with(|port| port.borrow().clone())
+}
+
+/// Runs `f` with the current output port set to `port`, restoring the
+/// previous one afterwards, even if `f` panics.
+pub fn with_output<T>(port: Port, f: impl FnOnce() -> T) -> T {
+    let _restore = RestoreOutput(CURRENT_OUTPUT.with(|current| current.replace(port)));
+    f()
+}
+
+/// Puts back the output port it holds when dropped.
+struct RestoreOutput(Port);
+
+impl Drop for RestoreOutput {
+    fn drop(&mut self) {
+        let previous = self.0.clone();
+        // `try_with` as the thread local may be gone if the thread is exiting.
+        let _ = CURRENT_OUTPUT.try_with(|current| current.replace(previous));
+    }
+}
+
+/// The port given as an optional last argument, or the current one.
+fn port_arg(arg: Option<&RispExp>, current: fn() -> Port, name: &str) -> Result<Port, RispErr> {
+    match arg {
+        None => Ok(current()),
+        Some(RispExp::Port(port)) => Ok(port.clone()),
+        Some(exp) => Err(RispErr::Type(format!(
+            "{name}: expected a port, got '{exp}'"
+        ))),
+    }
+}
+
+/// Adds the input and output builtins to a standard environment.
+pub(crate) fn insert_builtins(data: &mut HashMap<String, RispExp>) {
+    for (name, mode) in [("display", Mode::Display), ("write", Mode::Write)] {
+        insert_func(
+            data,
+            RispFunc::new(name, Arity::Between(1, 2), move |args, _| {
+                let port = port_arg(args.get(1), current_output, name)?;
+                port.write_str(&print(&args[0], mode))?;
+                Ok(RispExp::Nil)
+            }),
+        );
+    }
+    insert_func(
+        data,
+        RispFunc::new("newline", Arity::Between(0, 1), |args, _| {
+            port_arg(args.first(), current_output, "newline")?.write_str("\n")?;
+            Ok(RispExp::Nil)
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("print", Arity::AtLeast(0), |args, _| {
+            current_output().write_str(&join_printed(args, Mode::Display))?;
+            Ok(RispExp::Nil)
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("println", Arity::AtLeast(0), |args, _| {
+            let line = join_printed(args, Mode::Display) + "\n";
+            current_output().write_str(&line)?;
+            Ok(RispExp::Nil)
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("pprint", Arity::Between(1, 2), |args, env| {
+            // `(pprint x width)` overrides `*print-width*`.
+            let mut options = PrettyOptions::from_env(env);
+            if let Some(width) = args.get(1) {
+                options.width = parse_index(width, "pprint")?;
+            }
+            current_output().write_str(&(pretty(&args[0], &options) + "\n"))?;
+            Ok(RispExp::Nil)
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("read-line", Arity::Between(0, 1), |args, _| {
+            // `nil` at the end of the input.
+            let port = port_arg(args.first(), current_input, "read-line")?;
+            Ok(port.read_line()?.map_or(RispExp::Nil, RispExp::Literal))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("current-output-port", |[], _| {
+            Ok(RispExp::Port(current_output()))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("current-input-port", |[], _| {
+            Ok(RispExp::Port(current_input()))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("open-output-string", |[], _| {
+            Ok(RispExp::Port(Port::output_string()))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("open-input-string", |[s], _| match s {
+            RispExp::Literal(s) => Ok(RispExp::Port(Port::input_string(s.clone()))),
+            _ => Err(RispErr::Type(format!(
+                "open-input-string: expected a string, got '{s}'"
+            ))),
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("get-output-string", |[port], _| {
+            match port {
+                RispExp::Port(port) => port.contents(),
+                _ => None,
+            }
+            .map(RispExp::Literal)
+            .ok_or(RispErr::Type(format!(
+                "get-output-string: expected a string output port, got '{port}'"
+            )))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("with-output-to-string", |[thunk], env| {
+            // Calls `thunk` with no arguments and returns what it wrote.
+            let port = Port::output_string();
+            with_output(port.clone(), || apply(thunk, vec![], env))?;
+            Ok(RispExp::Literal(port.contents().unwrap_or_default()))
+        }),
+    );
+}
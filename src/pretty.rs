@@ -0,0 +1,274 @@
+//! A width-aware pretty printer, after Wadler's "A prettier printer". A
+//! value is first turned into a `Doc` whose groups either fit on the rest of
+//! the line or are broken at each of their line breaks, so short
+//! collections stay on one line and long ones are split, indented to show
+//! their nesting.
+
+use crate::number::Number;
+use crate::printer::{print_atom, Mode};
+use crate::risp_type::*;
+use std::collections::HashMap;
+
+/// How `pretty` lays values out. `length` and `level` abbreviate large
+/// values: a collection shows at most `length` items followed by `...`,
+/// and collections nested deeper than `level` print as `#`. Abbreviated
+/// output no longer reads back as the same value.
+#[derive(Clone, Debug)]
+pub struct PrettyOptions {
+    pub width: usize,
+    /// How far the arguments of a form such as `(define ...)`, and the
+    /// values of a map, are indented when broken over several lines.
+    pub indent: usize,
+    pub length: Option<usize>,
+    pub level: Option<usize>,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> PrettyOptions {
+        PrettyOptions {
+            width: 80,
+            indent: 2,
+            length: None,
+            level: None,
+        }
+    }
+}
+
+impl PrettyOptions {
+    /// The default options, with `width`, `indent`, `length` and `level`
+    /// taken from the `*print-width*`, `*print-indent*`, `*print-length*`
+    /// and `*print-level*` variables.
+    pub fn from_env(env: &RispEnv) -> PrettyOptions {
+        let limit = |name: &str| match env.get(name) {
+            Some(RispExp::Number(n)) => n.to_index(),
+            _ => None,
+        };
+        let default = PrettyOptions::default();
+        PrettyOptions {
+            width: limit("*print-width*").unwrap_or(default.width),
+            indent: limit("*print-indent*").unwrap_or(default.indent),
+            length: limit("*print-length*"),
+            level: limit("*print-level*"),
+        }
+    }
+}
+
+/// Pretty prints `exp` in its readable form.
+pub fn pretty(exp: &RispExp, options: &PrettyOptions) -> String {
+    layout(&to_doc(exp, options, 0), options.width)
+}
+
+enum Doc {
+    Text(String),
+    /// A space if the enclosing group fits on the line, and otherwise a
+    /// newline followed by the current indentation.
+    Line,
+    Nest(usize, Box<Doc>),
+    /// Sets the indentation to the current column.
+    Align(Box<Doc>),
+    Group(Box<Doc>),
+    Concat(Vec<Doc>),
+}
+
+fn text(s: impl Into<String>) -> Doc {
+    Doc::Text(s.into())
+}
+
+fn nest(indent: usize, doc: Doc) -> Doc {
+    Doc::Nest(indent, Box::new(doc))
+}
+
+fn align(doc: Doc) -> Doc {
+    Doc::Align(Box::new(doc))
+}
+
+fn group(doc: Doc) -> Doc {
+    Doc::Group(Box::new(doc))
+}
+
+/// `docs` separated by line breaks. With `fill`, each break is taken only
+/// if the next doc does not fit on the line, which packs runs of short
+/// items such as numbers; otherwise the enclosing group breaks all of them.
+fn lines(docs: Vec<Doc>, fill: bool) -> Doc {
+    let mut docs = docs.into_iter();
+    let first = docs.next();
+    Doc::Concat(first.into_iter().chain(breaks_before(docs, fill)).collect())
+}
+
+/// Each of `docs` preceded by a line break, taken as in `lines`.
+fn breaks_before(docs: impl Iterator<Item = Doc>, fill: bool) -> impl Iterator<Item = Doc> {
+    docs.flat_map(move |doc| match fill {
+        true => vec![group(Doc::Concat(vec![Doc::Line, doc]))],
+        false => vec![Doc::Line, doc],
+    })
+}
+
+fn is_collection(exp: &RispExp) -> bool {
+    matches!(
+        exp,
+        RispExp::List(..) | RispExp::Vector(_) | RispExp::Map(..) | RispExp::Set(..)
+    )
+}
+
+/// The docs of the `len` items of a collection, cut short by
+/// `options.length`.
+fn item_docs<T>(
+    len: usize,
+    items: impl Iterator<Item = T>,
+    options: &PrettyOptions,
+    doc: impl FnMut(T) -> Doc,
+) -> Vec<Doc> {
+    let shown = options.length.map_or(len, |max| max.min(len));
+    let mut docs: Vec<Doc> = items.take(shown).map(doc).collect();
+    if shown < len {
+        docs.push(text("..."));
+    }
+    docs
+}
+
+/// A collection whose items line up under the first one when broken.
+fn aligned(open: &str, items: Vec<Doc>, fill: bool, close: &str) -> Doc {
+    group(Doc::Concat(vec![
+        text(open),
+        align(lines(items, fill)),
+        text(close),
+    ]))
+}
+
+fn to_doc(exp: &RispExp, options: &PrettyOptions, depth: usize) -> Doc {
+    if is_collection(exp) && options.level.is_some_and(|level| depth >= level) {
+        return text("#");
+    }
+    let item = |x: &RispExp| to_doc(x, options, depth + 1);
+    match exp {
+        RispExp::List(list, _) => {
+            let mut items = item_docs(list.len(), list.iter(), options, item);
+            match list.first() {
+                // A form such as `(define (f x) ...)` keeps its first
+                // argument next to the head and indents the rest, which
+                // fill the lines if they are all atoms, as in `(vector 1 2
+                // ...)`.
+                Some(RispExp::Symbol(..)) if items.len() > 1 => {
+                    let rest = items.split_off(2);
+                    let first = items.pop().expect("the form has two items");
+                    let head = items.pop().expect("the form has two items");
+                    let fill = !list.iter().skip(2).any(is_collection);
+                    let rest = breaks_before(rest.into_iter(), fill);
+                    group(align(Doc::Concat(vec![
+                        text("("),
+                        head,
+                        text(" "),
+                        first,
+                        nest(options.indent, Doc::Concat(rest.collect())),
+                        text(")"),
+                    ])))
+                }
+                _ => aligned("(", items, !list.iter().any(is_collection), ")"),
+            }
+        }
+        RispExp::Vector(v) => {
+            let items = item_docs(v.len(), v.iter(), options, item);
+            aligned("[", items, !v.iter().any(is_collection), "]")
+        }
+        RispExp::Set(set, _) => {
+            let items = item_docs(set.len(), set.iter(), options, item);
+            aligned("#{", items, !set.iter().any(is_collection), "}")
+        }
+        RispExp::Map(map, _) => {
+            let pair = |(k, v)| {
+                group(Doc::Concat(vec![
+                    item(k),
+                    nest(options.indent, Doc::Concat(vec![Doc::Line, item(v)])),
+                ]))
+            };
+            aligned(
+                "{",
+                item_docs(map.len(), map.iter(), options, pair),
+                false,
+                "}",
+            )
+        }
+        _ => text(print_atom(exp, Mode::Write)),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Fit {
+    Flat,
+    Break,
+}
+
+fn layout(doc: &Doc, width: usize) -> String {
+    let mut out = String::new();
+    let mut col = 0;
+    let mut stack = vec![(0, Fit::Break, doc)];
+    while let Some((indent, fit, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(s) => {
+                out.push_str(s);
+                col += s.chars().count();
+            }
+            Doc::Line if fit == Fit::Flat => {
+                out.push(' ');
+                col += 1;
+            }
+            Doc::Line => {
+                out.push('\n');
+                out.extend(std::iter::repeat_n(' ', indent));
+                col = indent;
+            }
+            Doc::Nest(more, doc) => stack.push((indent + more, fit, doc)),
+            Doc::Align(doc) => stack.push((col, fit, doc)),
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, fit, doc))),
+            Doc::Group(inner) => {
+                let remaining = width as isize - col as isize;
+                let fit = if fit == Fit::Flat || fits(remaining, inner, &stack) {
+                    Fit::Flat
+                } else {
+                    Fit::Break
+                };
+                stack.push((indent, fit, inner));
+            }
+        }
+    }
+    out
+}
+
+/// Whether `doc` laid out flat, and whatever follows it up to the next line
+/// break, fits in `remaining` columns.
+fn fits(mut remaining: isize, doc: &Doc, rest: &[(usize, Fit, &Doc)]) -> bool {
+    let mut stack = vec![(Fit::Flat, doc)];
+    let mut rest = rest.iter().rev();
+    while remaining >= 0 {
+        let (fit, doc) = match stack.pop() {
+            Some(next) => next,
+            None => match rest.next() {
+                Some(&(_, fit, doc)) => (fit, doc),
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(s) => remaining -= s.chars().count() as isize,
+            Doc::Line if fit == Fit::Break => return true,
+            Doc::Line => remaining -= 1,
+            Doc::Nest(_, doc) | Doc::Align(doc) | Doc::Group(doc) => stack.push((fit, doc)),
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (fit, doc))),
+        }
+    }
+    false
+}
+
+/// Adds the variables that control `pprint` to a standard environment.
+pub(crate) fn insert_builtins(data: &mut HashMap<String, RispExp>) {
+    let default = PrettyOptions::default();
+    data.insert(
+        "*print-width*".to_string(),
+        RispExp::Number(Number::Int(default.width as i64)),
+    );
+    data.insert(
+        "*print-indent*".to_string(),
+        RispExp::Number(Number::Int(default.indent as i64)),
+    );
+    data.insert("*print-length*".to_string(), RispExp::Nil);
+    data.insert("*print-level*".to_string(), RispExp::Nil);
+}
@@ -0,0 +1,129 @@
+use crate::atom;
+use crate::risp_type::RispExp;
+use std::fmt::Write;
+
+/// How values are printed. `Write` gives the text the reader reads back as
+/// an equal value, which is what the REPL shows and `Display` produces;
+/// `Display` is for people, so strings and characters print as their
+/// contents.
+///
+/// Functions, error values and ports have no readable form and print the
+/// same way in both modes. Nor do symbols whose names
+/// would not read back as the same symbol, such as one made by
+/// `(string->symbol "a b")`: symbols always print as their bare name.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    Display,
+    Write,
+}
+
+/// The readable form of `exp`, as `pr-str` returns it.
+pub fn write_string(exp: &RispExp) -> String {
+    print(exp, Mode::Write)
+}
+
+/// The human form of `exp`, as `print-str` returns it.
+pub fn display_string(exp: &RispExp) -> String {
+    print(exp, Mode::Display)
+}
+
+pub fn print(exp: &RispExp, mode: Mode) -> String {
+    let mut out = String::new();
+    print_into(&mut out, exp, mode);
+    out
+}
+
+/// `args` printed and separated by spaces, as `pr-str` and `print-str`
+/// return them and `print` and `println` write them.
+pub fn join_printed(args: &[RispExp], mode: Mode) -> String {
+    let printed: Vec<String> = args.iter().map(|x| print(x, mode)).collect();
+    printed.join(" ")
+}
+
+fn print_into(out: &mut String, exp: &RispExp, mode: Mode) {
+    match exp {
+        RispExp::List(list, _) => print_seq(out, "(", list.iter(), ")", mode),
+        RispExp::Vector(v) => print_seq(out, "[", v.iter(), "]", mode),
+        RispExp::Set(set, _) => print_seq(out, "#{", set.iter(), "}", mode),
+        RispExp::Map(map, _) => {
+            out.push('{');
+            for (i, (k, v)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push(' ');
+                }
+                print_into(out, k, mode);
+                out.push(' ');
+                print_into(out, v, mode);
+            }
+            out.push('}');
+        }
+        _ => out.push_str(&print_atom(exp, mode)),
+    }
+}
+
+fn print_seq<'a>(
+    out: &mut String,
+    open: &str,
+    items: impl Iterator<Item = &'a RispExp>,
+    close: &str,
+    mode: Mode,
+) {
+    out.push_str(open);
+    for (i, x) in items.enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        print_into(out, x, mode);
+    }
+    out.push_str(close);
+}
+
+/// Prints a value that is not a collection.
+pub fn print_atom(exp: &RispExp, mode: Mode) -> String {
+    match exp {
+        RispExp::Nil => "nil".to_string(),
+        RispExp::Bool(b) => b.to_string(),
+        RispExp::Number(n) => n.to_string(),
+        RispExp::Symbol(s, _) => s.clone(),
+        RispExp::Keyword(k) => k.to_string(),
+        RispExp::Char(c) => match mode {
+            Mode::Display => c.to_string(),
+            Mode::Write => format!("#\\{}", atom::char_name(*c)),
+        },
+        RispExp::Literal(s) => match mode {
+            Mode::Display => s.clone(),
+            Mode::Write => quote_string(s),
+        },
+        RispExp::Pattern(p) => p.to_string(),
+        RispExp::Port(p) => p.to_string(),
+        RispExp::Func(f) => format!("#<function {}>", f.name),
+        RispExp::Lambda(_) => "#<lambda>".to_string(),
+        RispExp::Macro(_) => "#<macro>".to_string(),
+        RispExp::Error(e) => format!("#<error {} {}>", e.kind, e.message),
+        RispExp::List(..) | RispExp::Vector(_) | RispExp::Map(..) | RispExp::Set(..) => {
+            print(exp, mode)
+        }
+    }
+}
+
+/// A string literal with the escapes the reader understands.
+fn quote_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            '\0' => quoted.push_str("\\0"),
+            c if c.is_control() => {
+                write!(quoted, "\\u{{{:x}}}", c as u32).expect("writing to a String")
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,121 @@
+use crate::atom::Keyword;
+use crate::eval::apply;
+use crate::pattern::Pattern;
+use crate::printer::display_string;
+use crate::risp_type::*;
+use regex::Captures;
+use std::collections::HashMap;
+
+/// Takes a compiled pattern, or a string to compile on each call.
+fn parse_pattern(exp: &RispExp, name: &str) -> Result<Pattern, RispErr> {
+    match exp {
+        RispExp::Pattern(p) => Ok(p.clone()),
+        RispExp::Literal(s) => Pattern::new(s),
+        _ => Err(RispErr::Type(format!(
+            "{name}: expected a regex, got '{exp}'"
+        ))),
+    }
+}
+
+/// What a match returns: the matched text if the pattern has no groups, a
+/// map from keywords to the named groups if it has any, and otherwise a
+/// list of the matched text and each group. Groups that did not take part
+/// in the match are `nil`.
+fn match_value(pattern: &Pattern, caps: &Captures) -> RispExp {
+    let regex = pattern.regex();
+    let group = |m: Option<regex::Match>| m.map_or(RispExp::Nil, |m| string(m.as_str()));
+    if regex.captures_len() == 1 {
+        return group(caps.get(0));
+    }
+    if regex.capture_names().flatten().next().is_some() {
+        let named = regex
+            .capture_names()
+            .flatten()
+            .map(|name| (RispExp::Keyword(Keyword::new(name)), group(caps.name(name))))
+            .collect();
+        return RispExp::Map(named, None);
+    }
+    RispExp::List(caps.iter().map(group).collect(), None)
+}
+
+/// Adds the regular expression builtins to a standard environment.
+pub fn insert_builtins(data: &mut HashMap<String, RispExp>) {
+    insert_func(
+        data,
+        RispFunc::fixed("re-pattern", |[source], _| {
+            Ok(RispExp::Pattern(parse_pattern(source, "re-pattern")?))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("re-matches", |[pattern, s], _| {
+            // The whole string must match.
+            let pattern = parse_pattern(pattern, "re-matches")?;
+            let s = parse_str(s, "re-matches")?;
+            Ok(match pattern.anchored().captures(s) {
+                Some(caps) => match_value(&pattern, &caps),
+                None => RispExp::Nil,
+            })
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("re-find", |[pattern, s], _| {
+            let pattern = parse_pattern(pattern, "re-find")?;
+            let s = parse_str(s, "re-find")?;
+            Ok(match pattern.regex().captures(s) {
+                Some(caps) => match_value(&pattern, &caps),
+                None => RispExp::Nil,
+            })
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("re-seq", |[pattern, s], _| {
+            let pattern = parse_pattern(pattern, "re-seq")?;
+            let s = parse_str(s, "re-seq")?;
+            let matches = pattern
+                .regex()
+                .captures_iter(s)
+                .map(|caps| match_value(&pattern, &caps))
+                .collect();
+            Ok(RispExp::List(matches, None))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("re-replace", |[pattern, s, replacement], env| {
+            // The replacement is a string, in which `$1` and `${name}` refer
+            // to groups, or a function called with each match.
+            let pattern = parse_pattern(pattern, "re-replace")?;
+            let s = parse_str(s, "re-replace")?;
+            if let RispExp::Literal(replacement) = replacement {
+                let replaced = pattern.regex().replace_all(s, replacement.as_str());
+                return Ok(string(replaced));
+            }
+            let mut res = String::new();
+            let mut last = 0;
+            for caps in pattern.regex().captures_iter(s) {
+                let whole = caps.get(0).expect("group 0 is the whole match");
+                res.push_str(&s[last..whole.start()]);
+                let text = apply(replacement, vec![match_value(&pattern, &caps)], env)?;
+                res.push_str(&display_string(&text));
+                last = whole.end();
+            }
+            res.push_str(&s[last..]);
+            Ok(string(res))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("re-split", |[pattern, s], _| {
+            let pattern = parse_pattern(pattern, "re-split")?;
+            let parts = pattern
+                .regex()
+                .split(parse_str(s, "re-split")?)
+                .map(string)
+                .collect();
+            Ok(RispExp::List(parts, None))
+        }),
+    );
+}
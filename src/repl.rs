@@ -1,38 +1,12 @@
-use crate::eval::*;
-use crate::parser::*;
-use crate::risp_type::*;
 use rustyline::completion::{Completer, FilenameCompleter, Pair};
-use rustyline::config::OutputStreamType;
-use rustyline::error::ReadlineError;
 use rustyline::highlight::{Highlighter, MatchingBracketHighlighter};
 use rustyline::hint::{Hinter, HistoryHinter};
 use rustyline::validate::{self, MatchingBracketValidator, Validator};
-use rustyline::{Cmd, CompletionType, Config, Context, EditMode, Editor, KeyEvent};
+use rustyline::Context;
 use rustyline_derive::Helper;
 
 use std::borrow::Cow::{self, Borrowed, Owned};
 
-pub fn parse_eval(exp: String, env: &mut RispEnv) -> RispResult {
-    let mut token = tokenize(exp);
-    loop {
-        let (parsed_exp, remain) = parse(&token)?;
-        token = remain.to_vec();
-        if token.is_empty() {
-            return Ok(eval(&parsed_exp, env)?);
-        } else {
-            eval(&parsed_exp, env)?;
-        }
-    }
-}
-
-fn slurp_expr() -> String {
-    let mut expr = String::new();
-    std::io::stdin()
-        .read_line(&mut expr)
-        .expect("Failed to read line");
-    expr
-}
-
 //pub fn repl() {
 //    let env = &mut standard_env();
 //    loop {
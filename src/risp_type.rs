@@ -1,27 +1,87 @@
+use crate::atom::Keyword;
+use crate::list::RispList;
+use crate::number::Number;
+use crate::pattern::Pattern;
+use crate::port::Port;
+use crate::printer;
+use crate::span::Span;
 use anyhow::Result;
 use core::fmt;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
-use std::fmt::Display;
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+use std::mem;
 use std::rc::Rc;
 use thiserror::Error;
 
+/// A Risp value, which is also how code is represented once read.
+///
+/// Only symbols, lists and map and set literals carry the span they were
+/// read from, since those are the forms that fail when evaluated: a failing
+/// call is reported at its list, which covers any strings or numbers passed
+/// to it. Values made at run time have no span.
 #[derive(Clone)]
 pub enum RispExp {
     Nil,
     Bool(bool),
-    Symbol(String),
+    Symbol(String, Option<Span>),
+    Keyword(Keyword),
+    Char(char),
     Literal(String),
-    Number(f64),
-    List(Vec<RispExp>),
-    Func(fn(&[RispExp]) -> Result<RispExp, RispErr>),
+    Number(Number),
+    List(RispList, Option<Span>),
+    Vector(im_rc::Vector<RispExp>),
+    Func(RispFunc),
     Lambda(RispLambda),
+    Macro(RispLambda),
+    Map(im_rc::HashMap<RispExp, RispExp>, Option<Rc<LiteralForms>>),
+    Set(im_rc::HashSet<RispExp>, Option<Rc<LiteralForms>>),
+    Error(Rc<RispError>),
+    Pattern(Pattern),
+    Port(Port),
+}
+
+/// The forms of a map or set literal in the order they were read, which is
+/// the order they are evaluated in, and the span of the literal.
+pub struct LiteralForms {
+    pub forms: Vec<RispExp>,
+    pub span: Span,
+}
+
+/// An error as a value, made by `error` or by catching a failure in `try`.
+/// The `kind` tells failures apart, e.g. `:arithmetic-error`, `:type-error`
+/// or `:unbound-symbol`.
+#[derive(PartialEq, Eq, Hash)]
+pub struct RispError {
+    pub kind: Keyword,
+    pub message: String,
+    pub data: RispExp,
+}
+
+impl RispError {
+    pub fn value(kind: &str, message: impl Into<String>, data: RispExp) -> RispExp {
+        RispExp::Error(Rc::new(RispError {
+            kind: Keyword::new(kind),
+            message: message.into(),
+            data,
+        }))
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum RispErr {
     #[error("`{0}`")]
     Reason(String),
-    #[error("Unexpected Symbol='{0}'")]
+    /// An argument of the wrong type, such as a string passed to `+`.
+    #[error("`{0}`")]
+    Type(String),
+    /// An arithmetic operation with no result, such as division by zero.
+    #[error("`{0}`")]
+    Arithmetic(String),
+    #[error("unexpected symbol '{0}'")]
     UnexpectedSymbol(String),
     #[error("Unexpected Syntax")]
     UnexpectedSyntax,
@@ -31,8 +91,80 @@ pub enum RispErr {
     InvalidArgs,
     #[error("IO Error `{0}`")]
     IOError(#[from] std::io::Error),
-    #[error("unknown data store error")]
-    Unknown,
+    #[error("{0}: {1}\n{}", .0.snippet())]
+    At(Span, Box<RispErr>),
+    /// An error converting a value to a Rust type, with the path to the
+    /// offending part such as `servers[1].port`.
+    #[error("at `{0}`: {1}")]
+    AtField(String, Box<RispErr>),
+    /// A value raised by `throw`, which `try` catches as it is.
+    #[error("uncaught {0}")]
+    Thrown(RispExp),
+}
+
+impl RispErr {
+    /// Attaches the span of the offending form, unless the error already
+    /// points at a more specific one.
+    pub fn at(self, span: Span) -> RispErr {
+        match self {
+            RispErr::At(..) => self,
+            _ => RispErr::At(span, Box::new(self)),
+        }
+    }
+
+    /// Prefixes the path of a conversion error with the field or index it
+    /// happened in, e.g. `port` or `[1]`.
+    pub fn in_field(self, field: &str) -> RispErr {
+        match self {
+            RispErr::AtField(path, err) if path.starts_with('[') => {
+                RispErr::AtField(format!("{field}{path}"), err)
+            }
+            RispErr::AtField(path, err) => RispErr::AtField(format!("{field}.{path}"), err),
+            _ => RispErr::AtField(field.to_string(), Box::new(self)),
+        }
+    }
+
+    /// The value a `catch` clause binds for this error: thrown values as
+    /// they are, and other failures as an error value whose kind names the
+    /// variant.
+    pub fn into_value(self) -> RispExp {
+        let message = match &self {
+            RispErr::Reason(s) | RispErr::Type(s) | RispErr::Arithmetic(s) => s.clone(),
+            _ => self.to_string(),
+        };
+        let (kind, data) = match self {
+            RispErr::Reason(_) => ("error", RispExp::Nil),
+            RispErr::Type(_) => ("type-error", RispExp::Nil),
+            RispErr::Arithmetic(_) => ("arithmetic-error", RispExp::Nil),
+            RispErr::UnexpectedSymbol(s) => (
+                "unbound-symbol",
+                error_data("symbol", RispExp::Symbol(s, None)),
+            ),
+            RispErr::UnexpectedSyntax => ("syntax-error", RispExp::Nil),
+            RispErr::InvalidFunction(s) => (
+                "invalid-function",
+                error_data("function", RispExp::Literal(s)),
+            ),
+            RispErr::InvalidArgs => ("invalid-args", RispExp::Nil),
+            RispErr::IOError(_) => ("io-error", RispExp::Nil),
+            RispErr::At(span, err) => {
+                return match *err {
+                    RispErr::Thrown(value) => value,
+                    err => with_data(err.into_value(), "location", span.to_string()),
+                }
+            }
+            RispErr::AtField(path, err) => return with_data(err.into_value(), "path", path),
+            RispErr::Thrown(value) => return value,
+        };
+        RispError::value(kind, message, data)
+    }
+
+    pub fn within(self, span: &Option<Span>) -> RispErr {
+        match span {
+            Some(span) => self.at(span.clone()),
+            None => self,
+        }
+    }
 }
 
 //impl Display for RispErr {
@@ -45,48 +177,348 @@ pub enum RispErr {
 //    }
 //}
 
-#[derive(Clone)]
-pub struct RispEnv<'a> {
-    pub data: HashMap<String, RispExp>,
-    pub outer: Option<&'a RispEnv<'a>>,
+fn error_data(key: &str, value: RispExp) -> RispExp {
+    RispExp::Map(
+        im_rc::HashMap::unit(RispExp::Keyword(Keyword::new(key)), value),
+        None,
+    )
 }
 
-impl<'a> RispEnv<'a> {
+/// Adds `key` to the data of an error value, keeping any data it has.
+fn with_data(error: RispExp, key: &str, value: String) -> RispExp {
+    match &error {
+        RispExp::Error(e) => {
+            let mut data = match &e.data {
+                RispExp::Map(map, _) => map.clone(),
+                _ => im_rc::HashMap::new(),
+            };
+            data.insert(RispExp::Keyword(Keyword::new(key)), RispExp::Literal(value));
+            RispError::value(e.kind.name(), e.message.clone(), RispExp::Map(data, None))
+        }
+        _ => error,
+    }
+}
+
+struct RispFrame {
+    data: HashMap<String, RispExp>,
+    outer: Option<RispEnv>,
+}
+
+/// A handle to a frame of bindings. Cloning an env is cheap and shares the
+/// frame, so an env can be captured by lambdas and outlive the call that
+/// created it.
+#[derive(Clone)]
+pub struct RispEnv(Rc<RefCell<RispFrame>>);
+
+impl RispEnv {
+    pub fn new(data: HashMap<String, RispExp>) -> RispEnv {
+        RispEnv(Rc::new(RefCell::new(RispFrame { data, outer: None })))
+    }
+
+    /// Creates a child frame whose lookups fall back to `self`.
+    pub fn extend(&self, data: HashMap<String, RispExp>) -> RispEnv {
+        RispEnv(Rc::new(RefCell::new(RispFrame {
+            data,
+            outer: Some(self.clone()),
+        })))
+    }
+
     pub fn get(&self, key: &str) -> Option<RispExp> {
-        match self.data.get(key) {
+        let frame = self.0.borrow();
+        match frame.data.get(key) {
             Some(exp) => Some(exp.clone()),
-            None => match &self.outer {
+            None => match &frame.outer {
                 Some(outer_env) => outer_env.get(key),
                 None => None,
             },
         }
     }
+
+    /// Binds `key` in this frame, shadowing any outer binding.
+    pub fn define(&self, key: String, value: RispExp) {
+        self.0.borrow_mut().data.insert(key, value);
+    }
+
+    /// Assigns to the innermost frame that already binds `key`.
+    pub fn set(&self, key: &str, value: RispExp) -> Result<(), RispErr> {
+        let mut frame = self.0.borrow_mut();
+        if let Some(slot) = frame.data.get_mut(key) {
+            *slot = value;
+            return Ok(());
+        }
+        match &frame.outer {
+            Some(outer_env) => outer_env.set(key, value),
+            None => Err(RispErr::UnexpectedSymbol(key.to_string())),
+        }
+    }
+
+    /// Bindings of this frame only, sorted by name.
+    pub fn bindings(&self) -> Vec<(String, RispExp)> {
+        let mut bindings: Vec<(String, RispExp)> = self
+            .0
+            .borrow()
+            .data
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+        bindings
+    }
 }
 
 #[derive(Clone)]
 pub struct RispLambda {
     pub params_exp: Rc<RispExp>,
     pub body_exp: Rc<Vec<RispExp>>,
+    pub env: RispEnv,
+}
+
+impl RispLambda {
+    /// Whether both are the same closure, created by the same evaluation of
+    /// a `fn` form.
+    pub fn ptr_eq(&self, other: &RispLambda) -> bool {
+        Rc::ptr_eq(&self.body_exp, &other.body_exp)
+    }
 }
 
 pub type RispResult = Result<RispExp, RispErr>;
 
-impl fmt::Display for RispExp {
+/// How many arguments a builtin takes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arity {
+    Exact(usize),
+    AtLeast(usize),
+    Between(usize, usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, n: usize) -> bool {
+        match *self {
+            Arity::Exact(expected) => n == expected,
+            Arity::AtLeast(min) => n >= min,
+            Arity::Between(min, max) => (min..=max).contains(&n),
+        }
+    }
+}
+
+impl fmt::Display for Arity {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let str = match self {
-            RispExp::Nil => "nil".to_string(),
-            RispExp::Bool(b) => b.to_string(),
-            RispExp::Number(n) => n.to_string(),
-            RispExp::Symbol(s) => s.clone(),
-            RispExp::List(list) => {
-                let xs: Vec<String> = list.iter().map(|x| x.to_string()).collect();
-                format!("({})", xs.join(","))
-            }
-            RispExp::Func(_) => "Function".to_string(),
-            RispExp::Lambda(_) => "Lambda".to_string(),
-            RispExp::Literal(s) => s.clone(),
+        let (count, max) = match *self {
+            Arity::Exact(n) => (n.to_string(), n),
+            Arity::AtLeast(n) => (format!("at least {n}"), n),
+            Arity::Between(min, max) => (format!("{min} to {max}"), max),
         };
+        let plural = if max == 1 { "" } else { "s" };
+        write!(f, "{count} argument{plural}")
+    }
+}
+
+type NativeFn = dyn Fn(&[RispExp], &RispEnv) -> RispResult;
+
+/// A builtin written in Rust. Natives are passed the env they are called
+/// from and can call back into the evaluator with `eval::apply`, so they may
+/// take lambdas as arguments. Calls with the wrong number of arguments are
+/// rejected before the native runs.
+#[derive(Clone)]
+pub struct RispFunc {
+    pub name: Rc<str>,
+    pub arity: Arity,
+    func: Rc<NativeFn>,
+}
 
-        write!(f, "{str}")
+impl RispFunc {
+    pub fn new(
+        name: &str,
+        arity: Arity,
+        func: impl Fn(&[RispExp], &RispEnv) -> RispResult + 'static,
+    ) -> RispFunc {
+        RispFunc {
+            name: Rc::from(name),
+            arity,
+            func: Rc::new(func),
+        }
+    }
+
+    /// A builtin taking exactly `N` arguments, which it gets as an array.
+    pub fn fixed<const N: usize>(
+        name: &str,
+        func: impl Fn(&[RispExp; N], &RispEnv) -> RispResult + 'static,
+    ) -> RispFunc {
+        RispFunc::new(name, Arity::Exact(N), move |args, env| {
+            let args = <&[RispExp; N]>::try_from(args).expect("arity is checked by call");
+            func(args, env)
+        })
+    }
+
+    pub fn call(&self, args: &[RispExp], env: &RispEnv) -> RispResult {
+        if !self.arity.accepts(args.len()) {
+            return Err(RispErr::Reason(format!(
+                "expected {} to '{}', got {}",
+                self.arity,
+                self.name,
+                args.len()
+            )));
+        }
+        (self.func)(args, env)
+    }
+
+    pub fn ptr_eq(&self, other: &RispFunc) -> bool {
+        Rc::ptr_eq(&self.func, &other.func)
+    }
+}
+
+/// Binds `func` in `data` under its own name.
+pub fn insert_func(data: &mut HashMap<String, RispExp>, func: RispFunc) {
+    data.insert(func.name.to_string(), RispExp::Func(func));
+}
+
+/// The text of a string argument to the builtin `name`.
+pub(crate) fn parse_str<'a>(exp: &'a RispExp, name: &str) -> Result<&'a str, RispErr> {
+    match exp {
+        RispExp::Literal(s) => Ok(s),
+        _ => Err(RispErr::Type(format!(
+            "{name}: expected a string, got '{exp}'"
+        ))),
+    }
+}
+
+/// An index, count or width argument to the builtin `name`.
+pub(crate) fn parse_index(exp: &RispExp, name: &str) -> Result<usize, RispErr> {
+    match exp {
+        RispExp::Number(n) => n.to_index(),
+        _ => None,
+    }
+    .ok_or(RispErr::Type(format!(
+        "{name}: expected a non-negative integer, got '{exp}'"
+    )))
+}
+
+pub(crate) fn string(s: impl Into<String>) -> RispExp {
+    RispExp::Literal(s.into())
+}
+
+/// Structural equality: collections compare by contents, symbols by name
+/// (ignoring where they were read from) and functions by identity.
+impl PartialEq for RispExp {
+    fn eq(&self, other: &RispExp) -> bool {
+        match (self, other) {
+            (RispExp::Nil, RispExp::Nil) => true,
+            (RispExp::Bool(a), RispExp::Bool(b)) => a == b,
+            (RispExp::Symbol(a, _), RispExp::Symbol(b, _)) => a == b,
+            (RispExp::Keyword(a), RispExp::Keyword(b)) => a == b,
+            (RispExp::Char(a), RispExp::Char(b)) => a == b,
+            (RispExp::Literal(a), RispExp::Literal(b)) => a == b,
+            (RispExp::Number(a), RispExp::Number(b)) => a == b,
+            (RispExp::List(a, _), RispExp::List(b, _)) => a == b,
+            (RispExp::Vector(a), RispExp::Vector(b)) => a == b,
+            (RispExp::Func(a), RispExp::Func(b)) => a.ptr_eq(b),
+            (RispExp::Lambda(a), RispExp::Lambda(b)) => a.ptr_eq(b),
+            (RispExp::Macro(a), RispExp::Macro(b)) => a.ptr_eq(b),
+            (RispExp::Map(a, _), RispExp::Map(b, _)) => a == b,
+            (RispExp::Set(a, _), RispExp::Set(b, _)) => a == b,
+            (RispExp::Error(a), RispExp::Error(b)) => a == b,
+            (RispExp::Pattern(a), RispExp::Pattern(b)) => a == b,
+            (RispExp::Port(a), RispExp::Port(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for RispExp {}
+
+impl Hash for RispExp {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            RispExp::Nil => {}
+            RispExp::Bool(b) => b.hash(state),
+            RispExp::Symbol(s, _) => s.hash(state),
+            RispExp::Keyword(k) => k.hash(state),
+            RispExp::Char(c) => c.hash(state),
+            RispExp::Literal(s) => s.hash(state),
+            RispExp::Number(n) => n.hash(state),
+            RispExp::List(list, _) => list.hash(state),
+            RispExp::Vector(v) => v.hash(state),
+            RispExp::Func(f) => Rc::as_ptr(&f.func).cast::<()>().hash(state),
+            RispExp::Lambda(l) | RispExp::Macro(l) => Rc::as_ptr(&l.body_exp).hash(state),
+            RispExp::Map(m, _) => hash_unordered(m.len(), m.iter(), state),
+            RispExp::Set(s, _) => hash_unordered(s.len(), s.iter(), state),
+            RispExp::Error(e) => e.hash(state),
+            RispExp::Pattern(p) => p.hash(state),
+            RispExp::Port(p) => p.hash(state),
+        }
+    }
+}
+
+/// Hashes the items of a map or set regardless of their order. Equal maps
+/// can iterate in different orders, since each has its own random hasher,
+/// so the items are hashed with a fixed hasher and the hashes summed.
+fn hash_unordered<T: Hash, H: Hasher>(len: usize, items: impl Iterator<Item = T>, state: &mut H) {
+    let sum = items.fold(0u64, |sum, item| {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        sum.wrapping_add(hasher.finish())
+    });
+    len.hash(state);
+    sum.hash(state);
+}
+
+impl RispExp {
+    /// `nil` and `false` are false in conditions, every other value is true.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, RispExp::Nil | RispExp::Bool(false))
+    }
+
+    /// Identity, as tested by `eq?`. Collections and functions are identical
+    /// only if they are the same object; strings, symbols, keywords and
+    /// characters have no identity of their own and compare by value.
+    pub fn is_identical(&self, other: &RispExp) -> bool {
+        match (self, other) {
+            (RispExp::Number(a), RispExp::Number(b)) => a.is_identical(b),
+            (RispExp::List(a, _), RispExp::List(b, _)) => a.ptr_eq(b),
+            // Small vectors are stored inline, without an allocation to
+            // compare.
+            (RispExp::Vector(a), RispExp::Vector(b)) => {
+                a.ptr_eq(b) || (a.is_inline() && b.is_inline() && a == b)
+            }
+            (RispExp::Map(a, _), RispExp::Map(b, _)) => a.ptr_eq(b),
+            (RispExp::Set(a, _), RispExp::Set(b, _)) => a.ptr_eq(b),
+            (RispExp::Error(a), RispExp::Error(b)) => Rc::ptr_eq(a, b),
+            (RispExp::Pattern(a), RispExp::Pattern(b)) => a.ptr_eq(b),
+            _ => self == other,
+        }
+    }
+
+    /// `eqv?` is identity, except that numbers are compared by value (but
+    /// not across exactness, so `1` and `1.0` differ).
+    pub fn is_eqv(&self, other: &RispExp) -> bool {
+        match (self, other) {
+            (RispExp::Number(a), RispExp::Number(b)) => a == b,
+            _ => self.is_identical(other),
+        }
+    }
+
+    /// Equality as tested by `=`: numbers compare numerically, so
+    /// `(= 1 1.0)` holds, and everything else structurally.
+    pub fn is_equal(&self, other: &RispExp) -> bool {
+        match (self, other) {
+            (RispExp::Number(a), RispExp::Number(b)) => a.num_cmp(b) == Some(Ordering::Equal),
+            _ => self == other,
+        }
+    }
+}
+
+/// The readable form, as `write` prints it.
+impl fmt::Display for RispExp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&printer::write_string(self))
+    }
+}
+
+/// Shows the printed form, since functions and environments have no useful
+/// structure to show.
+impl fmt::Debug for RispExp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self}")
     }
 }
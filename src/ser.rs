@@ -0,0 +1,345 @@
+//! Converting Rust values to Risp with serde. Structs become maps keyed by
+//! keywords, sequences and tuples become vectors, `None` and `()` become
+//! `nil` and unit enum variants become keywords. Other variants become a
+//! one-entry map from the variant's keyword to its contents.
+
+use crate::atom::Keyword;
+use crate::number::Number;
+use crate::risp_type::{RispErr, RispExp};
+use im_rc::{HashMap as RispMap, Vector as RispVector};
+use num_bigint::BigInt;
+use serde::ser::{self, Serialize};
+use std::fmt;
+
+impl ser::Error for RispErr {
+    fn custom<T: fmt::Display>(msg: T) -> RispErr {
+        RispErr::Reason(msg.to_string())
+    }
+}
+
+/// Converts `value` to a Risp value, e.g. to pass a Rust struct to
+/// `Interpreter::define_global`.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<RispExp, RispErr> {
+    value.serialize(Serializer)
+}
+
+fn keyword(name: &str) -> RispExp {
+    RispExp::Keyword(Keyword::new(name))
+}
+
+fn variant(name: &str, value: RispExp) -> RispExp {
+    RispExp::Map(RispMap::unit(keyword(name), value), None)
+}
+
+fn int(n: impl Into<BigInt>) -> RispExp {
+    RispExp::Number(Number::from_big(n.into()))
+}
+
+/// The serializer behind `to_value`.
+pub struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = RispExp;
+    type Error = RispErr;
+
+    type SerializeSeq = SerializeVector;
+    type SerializeTuple = SerializeVector;
+    type SerializeTupleStruct = SerializeVector;
+    type SerializeTupleVariant = SerializeVariant<SerializeVector>;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeVariant<SerializeMap>;
+
+    fn serialize_bool(self, v: bool) -> Result<RispExp, RispErr> {
+        Ok(RispExp::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<RispExp, RispErr> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<RispExp, RispErr> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<RispExp, RispErr> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<RispExp, RispErr> {
+        Ok(RispExp::Number(Number::Int(v)))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<RispExp, RispErr> {
+        Ok(int(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<RispExp, RispErr> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<RispExp, RispErr> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<RispExp, RispErr> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<RispExp, RispErr> {
+        Ok(int(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<RispExp, RispErr> {
+        Ok(int(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<RispExp, RispErr> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<RispExp, RispErr> {
+        Ok(RispExp::Number(Number::Float(v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<RispExp, RispErr> {
+        Ok(RispExp::Char(v))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<RispExp, RispErr> {
+        Ok(RispExp::Literal(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<RispExp, RispErr> {
+        Ok(RispExp::Vector(
+            v.iter()
+                .map(|&b| RispExp::Number(Number::Int(b.into())))
+                .collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<RispExp, RispErr> {
+        Ok(RispExp::Nil)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<RispExp, RispErr> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<RispExp, RispErr> {
+        Ok(RispExp::Nil)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<RispExp, RispErr> {
+        Ok(RispExp::Nil)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<RispExp, RispErr> {
+        Ok(keyword(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<RispExp, RispErr> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        name: &'static str,
+        value: &T,
+    ) -> Result<RispExp, RispErr> {
+        Ok(variant(name, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SerializeVector, RispErr> {
+        Ok(SerializeVector {
+            items: RispVector::new(),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeVector, RispErr> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeVector, RispErr> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        name: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeVector>, RispErr> {
+        Ok(SerializeVariant {
+            name,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeMap, RispErr> {
+        Ok(SerializeMap {
+            map: RispMap::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeMap, RispErr> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        name: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeMap>, RispErr> {
+        Ok(SerializeVariant {
+            name,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+pub struct SerializeVector {
+    items: RispVector<RispExp>,
+}
+
+impl ser::SerializeSeq for SerializeVector {
+    type Ok = RispExp;
+    type Error = RispErr;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RispErr> {
+        self.items.push_back(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<RispExp, RispErr> {
+        Ok(RispExp::Vector(self.items))
+    }
+}
+
+impl ser::SerializeTuple for SerializeVector {
+    type Ok = RispExp;
+    type Error = RispErr;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RispErr> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<RispExp, RispErr> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeVector {
+    type Ok = RispExp;
+    type Error = RispErr;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RispErr> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<RispExp, RispErr> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+pub struct SerializeMap {
+    map: RispMap<RispExp, RispExp>,
+    key: Option<RispExp>,
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = RispExp;
+    type Error = RispErr;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), RispErr> {
+        self.key = Some(to_value(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RispErr> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value is called after serialize_key");
+        self.map.insert(key, to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<RispExp, RispErr> {
+        Ok(RispExp::Map(self.map, None))
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = RispExp;
+    type Error = RispErr;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), RispErr> {
+        self.map.insert(keyword(key), to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<RispExp, RispErr> {
+        Ok(RispExp::Map(self.map, None))
+    }
+}
+
+/// A tuple or struct variant, wrapped in a map from the variant's name
+/// when done.
+pub struct SerializeVariant<S> {
+    name: &'static str,
+    inner: S,
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeVector> {
+    type Ok = RispExp;
+    type Error = RispErr;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RispErr> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<RispExp, RispErr> {
+        Ok(variant(self.name, ser::SerializeSeq::end(self.inner)?))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeMap> {
+    type Ok = RispExp;
+    type Error = RispErr;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), RispErr> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<RispExp, RispErr> {
+        Ok(variant(self.name, ser::SerializeStruct::end(self.inner)?))
+    }
+}
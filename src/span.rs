@@ -0,0 +1,73 @@
+use core::fmt;
+use std::rc::Rc;
+
+/// A piece of source text, a file loaded with `load` or a line typed into
+/// the REPL.
+pub struct Source {
+    pub name: String,
+    pub text: String,
+}
+
+impl Source {
+    pub fn new(name: impl Into<String>, text: impl Into<String>) -> Rc<Source> {
+        Rc::new(Source {
+            name: name.into(),
+            text: text.into(),
+        })
+    }
+}
+
+/// Where a token or expression came from. `start` and `end` are byte
+/// offsets into the source text, `line` and `col` are 1-based.
+#[derive(Clone)]
+pub struct Span {
+    pub source: Rc<Source>,
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Span {
+    /// A span covering `self` up to the end of `other`.
+    pub fn to(&self, other: &Span) -> Span {
+        Span {
+            end: other.end,
+            ..self.clone()
+        }
+    }
+
+    /// The source line of the span with the spanned text underlined by
+    /// carets. Spans running past the end of the line are cut off there.
+    pub fn snippet(&self) -> String {
+        let text = &self.source.text;
+        let line_start = text[..self.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = text[self.start..]
+            .find('\n')
+            .map_or(text.len(), |i| self.start + i);
+        let line = &text[line_start..line_end];
+        let underlined = text[self.start..self.end.clamp(self.start, line_end)]
+            .chars()
+            .count()
+            .max(1);
+        let gutter = self.line.to_string();
+        format!(
+            "{pad} |\n{gutter} | {line}\n{pad} | {space}{carets}",
+            pad = " ".repeat(gutter.len()),
+            space = " ".repeat(self.col - 1),
+            carets = "^".repeat(underlined),
+        )
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.source.name, self.line, self.col)
+    }
+}
+
+impl fmt::Debug for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Span({self})")
+    }
+}
@@ -0,0 +1,263 @@
+//! String builtins. Lengths and indices count Unicode scalar values
+//! (Rust `char`s), not bytes or grapheme clusters, so `"é"` written as `e`
+//! plus a combining accent has length 2, and indexing never splits a UTF-8
+//! sequence.
+
+use crate::lexer::tokenize;
+use crate::number::Number;
+use crate::parser::{parse, skip_discarded};
+use crate::printer::{display_string, join_printed, write_string, Mode};
+use crate::risp_type::*;
+use crate::span::Source;
+use std::collections::HashMap;
+
+/// The text `str` and `format`'s `~a` show for a value: its display form,
+/// except that `nil` is nothing.
+fn display(exp: &RispExp) -> String {
+    match exp {
+        RispExp::Nil => String::new(),
+        _ => display_string(exp),
+    }
+}
+
+/// `(format template args...)` fills `~a` with an argument as `display`
+/// shows it and `~s` as `write` does; `~%` is a newline and `~~` a tilde.
+fn format(template: &str, args: &[RispExp]) -> RispResult {
+    let mut res = String::new();
+    let mut args = args.iter();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '~' {
+            res.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('~') => res.push('~'),
+            Some('%') => res.push('\n'),
+            Some(directive @ ('a' | 's')) => {
+                let arg = args.next().ok_or(RispErr::Reason(
+                    "format: too few arguments for the template".to_string(),
+                ))?;
+                res.push_str(&if directive == 'a' {
+                    display(arg)
+                } else {
+                    write_string(arg)
+                });
+            }
+            Some(other) => {
+                return Err(RispErr::Reason(format!(
+                    "format: unknown directive '~{other}'"
+                )))
+            }
+            None => {
+                return Err(RispErr::Reason(
+                    "format: template ends with '~'".to_string(),
+                ))
+            }
+        }
+    }
+    if args.next().is_some() {
+        return Err(RispErr::Reason(
+            "format: too many arguments for the template".to_string(),
+        ));
+    }
+    Ok(string(res))
+}
+
+/// Adds the string builtins to a standard environment.
+pub fn insert_builtins(data: &mut HashMap<String, RispExp>) {
+    insert_func(
+        data,
+        RispFunc::new("str", Arity::AtLeast(0), |args, _| {
+            Ok(string(args.iter().map(display).collect::<String>()))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("pr-str", Arity::AtLeast(0), |args, _| {
+            Ok(string(join_printed(args, Mode::Write)))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("print-str", Arity::AtLeast(0), |args, _| {
+            Ok(string(join_printed(args, Mode::Display)))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("read-string", |[s], _| {
+            // Reads a single datum, the inverse of `pr-str`. Comments may
+            // surround it, but nothing else.
+            let source = Source::new("<read-string>", parse_str(s, "read-string")?);
+            let tokens = tokenize(&source)?;
+            let tokens = skip_discarded(&tokens)?;
+            if tokens.is_empty() {
+                return Err(RispErr::Reason("read-string: no datum to read".to_string()));
+            }
+            let (exp, rest) = parse(tokens)?;
+            if let Some(extra) = skip_discarded(rest)?.first() {
+                return Err(RispErr::Reason(
+                    "read-string: unexpected data after the datum".to_string(),
+                )
+                .at(extra.span.clone()));
+            }
+            Ok(exp)
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("string-length", |[s], _| {
+            let len = parse_str(s, "string-length")?.chars().count();
+            Ok(RispExp::Number(Number::Int(len as i64)))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("substring", Arity::Between(2, 3), |args, _| {
+            let (s, start, end) = match args {
+                [s, start] => {
+                    let s = parse_str(s, "substring")?;
+                    (s, start, s.chars().count())
+                }
+                [s, start, end] => (
+                    parse_str(s, "substring")?,
+                    start,
+                    parse_index(end, "substring")?,
+                ),
+                _ => unreachable!("arity is checked by call"),
+            };
+            let start = parse_index(start, "substring")?;
+            let len = s.chars().count();
+            if start > end || end > len {
+                return Err(RispErr::Reason(format!(
+                    "substring: range {start}..{end} out of bounds for a string of length {len}"
+                )));
+            }
+            Ok(string(
+                s.chars().skip(start).take(end - start).collect::<String>(),
+            ))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("string-split", Arity::Between(1, 2), |args, _| {
+            // Without a separator, splits on runs of whitespace.
+            let parts: Vec<&str> = match args {
+                [s] => parse_str(s, "string-split")?.split_whitespace().collect(),
+                [s, sep] => {
+                    let sep = parse_str(sep, "string-split")?;
+                    if sep.is_empty() {
+                        return Err(RispErr::Reason("string-split: empty separator".to_string()));
+                    }
+                    parse_str(s, "string-split")?.split(sep).collect()
+                }
+                _ => unreachable!("arity is checked by call"),
+            };
+            Ok(RispExp::List(parts.into_iter().map(string).collect(), None))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("string-join", Arity::Between(1, 2), |args, _| {
+            let (items, sep) = match args {
+                [items] => (items, ""),
+                [items, sep] => (items, parse_str(sep, "string-join")?),
+                _ => unreachable!("arity is checked by call"),
+            };
+            let items: Vec<String> = match items {
+                RispExp::List(list, _) => list.iter().map(display).collect(),
+                RispExp::Vector(v) => v.iter().map(display).collect(),
+                RispExp::Nil => vec![],
+                _ => {
+                    return Err(RispErr::Type(format!(
+                        "string-join: expected a list, got '{items}'"
+                    )))
+                }
+            };
+            Ok(string(items.join(sep)))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("string-trim", |[s], _| {
+            Ok(string(parse_str(s, "string-trim")?.trim()))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("string-upcase", |[s], _| {
+            Ok(string(parse_str(s, "string-upcase")?.to_uppercase()))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("string-downcase", |[s], _| {
+            Ok(string(parse_str(s, "string-downcase")?.to_lowercase()))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("string-contains?", |[s, sub], _| {
+            let s = parse_str(s, "string-contains?")?;
+            Ok(RispExp::Bool(
+                s.contains(parse_str(sub, "string-contains?")?),
+            ))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("string-replace", |[s, from, to], _| {
+            // Replaces every occurrence.
+            let s = parse_str(s, "string-replace")?;
+            let from = parse_str(from, "string-replace")?;
+            if from.is_empty() {
+                return Err(RispErr::Reason("string-replace: empty pattern".to_string()));
+            }
+            Ok(string(s.replace(from, parse_str(to, "string-replace")?)))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("string->number", |[s], _| {
+            // `nil` if the string is not a number.
+            Ok(Number::parse(parse_str(s, "string->number")?.trim())
+                .map(RispExp::Number)
+                .unwrap_or(RispExp::Nil))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("number->string", |[n], _| match n {
+            RispExp::Number(n) => Ok(string(n.to_string())),
+            _ => Err(RispErr::Type(format!(
+                "number->string: expected a number, got '{n}'"
+            ))),
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("string->symbol", |[s], _| {
+            Ok(RispExp::Symbol(
+                parse_str(s, "string->symbol")?.to_string(),
+                None,
+            ))
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::fixed("symbol->string", |[sym], _| match sym {
+            RispExp::Symbol(s, _) => Ok(string(s.clone())),
+            _ => Err(RispErr::Type(format!(
+                "symbol->string: expected a symbol, got '{sym}'"
+            ))),
+        }),
+    );
+    insert_func(
+        data,
+        RispFunc::new("format", Arity::AtLeast(1), |args, _| {
+            let (template, args) = args.split_first().expect("arity is checked by call");
+            format(parse_str(template, "format")?, args)
+        }),
+    );
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::{eval, eval_exp};
+use risp::{Interpreter, RispExp};
+
+#[test]
+fn equal_maps_and_sets_hash_alike() {
+    let set = eval_exp(
+        "(hash-set {:a 1 :b 2 :c 3 :d 4 :e 5}
+                   {:e 5 :d 4 :c 3 :b 2 :a 1}
+                   #{1 2 3 4 5 6 7 8}
+                   #{8 7 6 5 4 3 2 1}
+                   (assoc (hash-map) :x 1 :y 2 :z 3)
+                   (assoc (hash-map) :z 3 :y 2 :x 1))",
+    );
+    match set {
+        RispExp::Set(set, _) => assert_eq!(set.len(), 3),
+        other => panic!("expected a set, got {}", other),
+    }
+    assert_eq!(
+        eval_exp("(contains? #{{:a 1 :b 2 :c 3 :d 4 :e 5}} {:e 5 :d 4 :c 3 :b 2 :a 1})"),
+        RispExp::Bool(true)
+    );
+    assert_eq!(
+        eval("(get {#{1 2 3 4 5 6 7 8} :found} #{8 7 6 5 4 3 2 1})"),
+        ":found"
+    );
+}
+
+#[test]
+fn literals_evaluate_in_source_order() {
+    let interp = Interpreter::new();
+    interp.eval_str("(def order [])").unwrap();
+    interp
+        .eval_str(
+            "(def (note x) (set! order (conj order x)) x)
+             {(note 1) (note 2) (note 3) (note 4) (note 5) (note 6)}
+             #{(note 7) (note 8) (note 9) (note 10)}",
+        )
+        .unwrap();
+    assert_eq!(
+        interp.get_global("order").unwrap().to_string(),
+        "[1 2 3 4 5 6 7 8 9 10]"
+    );
+}
+
+#[test]
+fn duplicate_evaluated_keys_are_an_error() {
+    let interp = Interpreter::new();
+    let err = interp.eval_str("{(+ 1 1) :a 2 :b}").unwrap_err();
+    assert!(err.to_string().contains("duplicate key '2'"), "{}", err);
+    let err = interp.eval_str("#{(+ 1 1) 2}").unwrap_err();
+    assert!(err.to_string().contains("duplicate element '2'"), "{}", err);
+}
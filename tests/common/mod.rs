@@ -0,0 +1,19 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use risp::{Interpreter, RispExp};
+
+/// The value of `source` in a fresh interpreter.
+pub fn eval_exp(source: &str) -> RispExp {
+    Interpreter::new().eval_str(source).unwrap()
+}
+
+/// The value of `source` in a fresh interpreter, as the REPL prints it.
+pub fn eval(source: &str) -> String {
+    eval_exp(source).to_string()
+}
+
+/// The value of `source` in `interp`, as the REPL prints it.
+pub fn eval_in(interp: &Interpreter, source: &str) -> String {
+    interp.eval_str(source).unwrap().to_string()
+}
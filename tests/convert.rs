@@ -0,0 +1,28 @@
+use num_bigint::BigInt;
+use risp::{FromRisp, Interpreter, Number, RispExp};
+use std::rc::Rc;
+
+#[test]
+fn registered_closures_convert_their_arguments() {
+    let interp = Interpreter::new();
+    interp.register_fn("add", |a: i64, b: i64| a + b);
+    interp.register_fn("greet", |name: String| format!("hello, {}", name));
+    assert_eq!(interp.eval_str("(add 2 3)").unwrap().to_string(), "5");
+    assert_eq!(
+        interp.eval_str("(greet \"risp\")").unwrap().to_string(),
+        "\"hello, risp\""
+    );
+    let err = interp.eval_str("(add 1.5 2)").unwrap_err();
+    assert!(err.to_string().contains("expected an integer"), "{}", err);
+}
+
+#[test]
+fn big_integers_convert_to_i64_when_they_fit() {
+    let big = |n: BigInt| RispExp::Number(Number::Big(Rc::new(n)));
+    assert_eq!(i64::from_risp(&big(BigInt::from(42))).unwrap(), 42);
+    assert_eq!(
+        i64::from_risp(&big(BigInt::from(i64::MIN))).unwrap(),
+        i64::MIN
+    );
+    assert!(i64::from_risp(&big(BigInt::from(i64::MAX) + 1)).is_err());
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use common::eval;
+
+fn holds(form: &str) -> bool {
+    match eval(form).as_str() {
+        "true" => true,
+        "false" => false,
+        other => panic!("{} gave {}", form, other),
+    }
+}
+
+#[test]
+fn eq_is_identity() {
+    assert!(holds("(eq? 'a 'a)"));
+    assert!(holds("(eq? :k :k)"));
+    assert!(holds("(eq? 1 1)"));
+    assert!(holds("(eq? nil nil)"));
+    assert!(holds("(let ((l (list 1 2))) (eq? l l))"));
+    assert!(!holds("(eq? (list 1) (list 1))"));
+    assert!(!holds("(eq? {:a (list 1)} {:a (list 1)})"));
+    // Exact numbers that need an allocation have identity of their own.
+    assert!(!holds("(eq? 2/3 2/3)"));
+    assert!(!holds("(eq? 1 1.0)"));
+}
+
+#[test]
+fn eqv_compares_numbers_of_the_same_exactness() {
+    assert!(holds("(eqv? 2/3 2/3)"));
+    assert!(holds("(eqv? 100000000000000000000 100000000000000000000)"));
+    assert!(holds("(eqv? 1.5 1.5)"));
+    assert!(!holds("(eqv? 1 1.0)"));
+    assert!(!holds("(eqv? 1/2 0.5)"));
+    assert!(!holds("(eqv? (list 1) (list 1))"));
+}
+
+#[test]
+fn equal_compares_structure() {
+    assert!(holds(r#"(equal? "a" "a")"#));
+    assert!(holds("(equal? [1 [2 {:a #{3}}]] [1 [2 {:a #{3}}]])"));
+    assert!(holds("(equal? '(1 (2)) (list 1 (list 2)))"));
+    assert!(holds("(equal? {:a 1 :b 2} {:b 2 :a 1})"));
+    // Lists and vectors are different types, as are exact and inexact
+    // numbers.
+    assert!(!holds("(equal? [1 2] (list 1 2))"));
+    assert!(!holds("(equal? 1 1.0)"));
+    assert!(!holds("(equal? {:a 1} {:a 2})"));
+}
+
+#[test]
+fn numeric_equality_ignores_exactness() {
+    assert!(holds("(= 1 1.0)"));
+    assert!(holds("(= 1/2 0.5)"));
+    assert!(holds("(= 1 1.0 2/2)"));
+    assert!(holds("(= 100000000000000000000 1e20)"));
+    assert!(!holds("(= 1 1.0 2)"));
+    assert!(!holds("(= (/ 0.0 0.0) (/ 0.0 0.0))"));
+}
+
+#[test]
+fn equality_works_on_every_type() {
+    assert!(holds(r#"(= "a" "a" "a")"#));
+    assert!(holds("(= 'a 'a)"));
+    assert!(holds("(= :a :a)"));
+    assert!(holds("(= nil nil)"));
+    assert!(holds("(= '(1 2) '(1 2))"));
+    assert!(holds("(= {:a [1]} {:a [1]})"));
+    assert!(!holds(r#"(= "a" 'a)"#));
+    assert!(!holds("(= nil false)"));
+    assert!(holds(r#"(not= "a" "b")"#));
+    assert!(!holds("(not= 1 1.0)"));
+}
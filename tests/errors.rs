@@ -0,0 +1,64 @@
+mod common;
+
+use common::eval;
+use risp::Interpreter;
+
+fn kind_of(form: &str) -> String {
+    eval(&format!("(try {} (catch e (error-kind e)))", form))
+}
+
+#[test]
+fn caught_failures_have_kinds() {
+    assert_eq!(kind_of("(/ 1 0)"), ":arithmetic-error");
+    assert_eq!(kind_of("(quotient 1 0)"), ":arithmetic-error");
+    assert_eq!(kind_of("(+ 1 \"a\")"), ":type-error");
+    assert_eq!(kind_of("(string-length 1)"), ":type-error");
+    assert_eq!(kind_of("undefined-thing"), ":unbound-symbol");
+    assert_eq!(kind_of("(1 2)"), ":invalid-function");
+    assert_eq!(kind_of("(read-file \"/no/such/file\")"), ":io-error");
+    assert_eq!(kind_of("(throw (error \"boom\"))"), ":error");
+}
+
+#[test]
+fn caught_errors_keep_their_message_and_data() {
+    assert_eq!(
+        eval("(try (/ 1 0) (catch e (error-message e)))"),
+        "\"division by zero\""
+    );
+    assert_eq!(
+        eval("(try (throw (error \"bad\" {:code 7})) (catch e (get (error-data e) :code)))"),
+        "7"
+    );
+    assert_eq!(
+        eval("(try undefined-thing (catch e (get (error-data e) :symbol)))"),
+        "undefined-thing"
+    );
+}
+
+#[test]
+fn thrown_values_are_caught_as_they_are() {
+    assert_eq!(eval("(try (throw {:a 1}) (catch e e))"), "{:a 1}");
+    assert_eq!(eval("(try (+ 1 (throw 41)) (catch e (+ e 1)))"), "42");
+}
+
+#[test]
+fn finally_runs_whether_or_not_anything_failed() {
+    let interp = Interpreter::new();
+    interp.eval_str("(def log [])").unwrap();
+    interp
+        .eval_str("(try 1 (finally (set! log (conj log :ok))))")
+        .unwrap();
+    let res = interp.eval_str("(try (throw 1) (finally (set! log (conj log :failed))))");
+    assert!(res.unwrap_err().to_string().contains("uncaught 1"));
+    assert_eq!(
+        interp
+            .eval_str("(try (throw 1) (catch e e) (finally (set! log (conj log :caught))))")
+            .unwrap()
+            .to_string(),
+        "1"
+    );
+    assert_eq!(
+        interp.get_global("log").unwrap().to_string(),
+        "[:ok :failed :caught]"
+    );
+}
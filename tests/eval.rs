@@ -0,0 +1,188 @@
+mod common;
+
+use common::{eval, eval_in};
+use risp::Interpreter;
+
+/// Far deeper than a test thread's stack allows for calls that are not in
+/// tail position, which overflow within a few hundred levels.
+const DEPTH: u32 = 10_000;
+
+#[test]
+fn self_tail_calls_run_in_constant_stack() {
+    let interp = Interpreter::new();
+    interp
+        .eval_str("(def (count-down n) (if (= n 0) :done (count-down (- n 1))))")
+        .unwrap();
+    assert_eq!(
+        eval_in(&interp, &format!("(count-down {})", DEPTH)),
+        ":done"
+    );
+}
+
+#[test]
+fn mutual_tail_calls_run_in_constant_stack() {
+    let interp = Interpreter::new();
+    interp
+        .eval_str(
+            "(def (my-even? n) (if (= n 0) true (my-odd? (- n 1))))
+             (def (my-odd? n) (if (= n 0) false (my-even? (- n 1))))",
+        )
+        .unwrap();
+    assert_eq!(eval_in(&interp, &format!("(my-even? {})", DEPTH)), "true");
+    assert_eq!(
+        eval_in(&interp, &format!("(my-odd? {})", DEPTH + 1)),
+        "true"
+    );
+}
+
+#[test]
+fn tail_position_includes_do_let_cond_and_when() {
+    let interp = Interpreter::new();
+    interp
+        .eval_str(
+            "(def (via-do n acc) (do (if (= n 0) acc (via-do (- n 1) (+ acc 1)))))
+             (def (via-let n) (let ((m (- n 1))) (if (< m 0) :let (via-let m))))
+             (def (via-cond n) (cond ((= n 0) :cond) (else (via-cond (- n 1)))))
+             (def (via-when n) (when (> n 0) (via-when (- n 1))))",
+        )
+        .unwrap();
+    assert_eq!(
+        eval_in(&interp, &format!("(via-do {} 0)", DEPTH)),
+        DEPTH.to_string()
+    );
+    assert_eq!(eval_in(&interp, &format!("(via-let {})", DEPTH)), ":let");
+    assert_eq!(eval_in(&interp, &format!("(via-cond {})", DEPTH)), ":cond");
+    assert_eq!(eval_in(&interp, &format!("(via-when {})", DEPTH)), "nil");
+}
+
+#[test]
+fn set_changes_the_binding_where_it_lives() {
+    let interp = Interpreter::new();
+    interp
+        .eval_str("(def x 1) (def (bump) (set! x (+ x 1)))")
+        .unwrap();
+    // `bump` sees the global `x`, not the caller's local one.
+    assert_eq!(eval_in(&interp, "(let ((x 10)) (bump) x)"), "10");
+    assert_eq!(eval_in(&interp, "x"), "2");
+    assert_eq!(eval_in(&interp, "(let ((z 1)) (set! z 2) z)"), "2");
+    let err = interp.eval_str("(set! unbound-thing 1)").unwrap_err();
+    assert!(err.to_string().contains("unbound-thing"), "{}", err);
+    assert!(interp.get_global("unbound-thing").is_none());
+}
+
+#[test]
+fn let_forms_differ_in_what_bindings_see() {
+    let interp = Interpreter::new();
+    assert!(interp.eval_str("(let ((a 1) (b a)) b)").is_err());
+    assert_eq!(eval_in(&interp, "(let* ((a 1) (b (+ a 1))) b)"), "2");
+    assert_eq!(
+        eval_in(
+            &interp,
+            "(letrec ((ev? (fn (n) (if (= n 0) true (od? (- n 1)))))
+                      (od? (fn (n) (if (= n 0) false (ev? (- n 1))))))
+               (ev? 11))"
+        ),
+        "false"
+    );
+}
+
+#[test]
+fn def_with_a_list_defines_a_function() {
+    let interp = Interpreter::new();
+    interp.eval_str("(def (double x) (* x 2))").unwrap();
+    assert_eq!(eval_in(&interp, "(double 4)"), "8");
+    interp
+        .eval_str("(def (tagged tag & items) [tag (count items)])")
+        .unwrap();
+    assert_eq!(eval_in(&interp, "(tagged :a 1 2 3)"), "[:a 3]");
+}
+
+#[test]
+fn closures_keep_their_own_state() {
+    let interp = Interpreter::new();
+    interp
+        .eval_str(
+            "(def (make-counter) (let ((n 0)) (fn () (set! n (+ n 1)) n)))
+             (def a (make-counter))
+             (def b (make-counter))",
+        )
+        .unwrap();
+    assert_eq!(eval_in(&interp, "[(a) (a) (b) (a)]"), "[1 2 1 3]");
+}
+
+#[test]
+fn closures_capture_arguments() {
+    let interp = Interpreter::new();
+    interp
+        .eval_str("(def (adder a) (fn (b) (+ a b))) (def add3 (adder 3))")
+        .unwrap();
+    assert_eq!(eval_in(&interp, "(add3 4)"), "7");
+    assert_eq!(eval_in(&interp, "(((fn (a) (fn (b) (- a b))) 10) 4)"), "6");
+}
+
+#[test]
+fn lookup_is_lexical_not_dynamic() {
+    let interp = Interpreter::new();
+    interp
+        .eval_str(
+            "(def y 1)
+             (def (get-y) y)
+             (def (outer) (let ((y 5)) (fn () y)))",
+        )
+        .unwrap();
+    // The caller's `y` is not visible to `get-y`...
+    assert_eq!(eval_in(&interp, "(let ((y 2)) (get-y))"), "1");
+    // ...while a closure sees the `y` where it was made, after that frame
+    // has returned.
+    assert_eq!(eval_in(&interp, "(let ((y 2)) ((outer)))"), "5");
+}
+
+#[test]
+fn and_and_or_return_the_deciding_value() {
+    assert_eq!(eval("(and)"), "true");
+    assert_eq!(eval("(and 1 2 3)"), "3");
+    assert_eq!(eval("(and 1 false 3)"), "false");
+    assert_eq!(eval("(and nil 2)"), "nil");
+    assert_eq!(eval("(or)"), "false");
+    assert_eq!(eval("(or false nil)"), "nil");
+    assert_eq!(eval("(or false 2 3)"), "2");
+    assert_eq!(eval("(or nil false)"), "false");
+}
+
+#[test]
+fn and_and_or_short_circuit() {
+    let interp = Interpreter::new();
+    interp.eval_str("(def hit false)").unwrap();
+    assert_eq!(eval_in(&interp, "(and false (set! hit true))"), "false");
+    assert_eq!(eval_in(&interp, "(or 1 (set! hit true))"), "1");
+    assert_eq!(eval_in(&interp, "hit"), "false");
+}
+
+#[test]
+fn cond_clauses() {
+    assert_eq!(eval("(cond (false 1) (nil 2) (else 3))"), "3");
+    assert_eq!(eval("(cond (false 1) (:x 2 3))"), "3");
+    // A clause without a body gives the value of its test.
+    assert_eq!(eval("(cond (false 1) ((+ 1 1)))"), "2");
+    assert_eq!(eval("(cond (false 1))"), "nil");
+}
+
+#[test]
+fn only_nil_and_false_are_falsey() {
+    assert_eq!(eval("(if nil 1 2)"), "2");
+    assert_eq!(eval("(if false 1 2)"), "2");
+    for value in ["0", "\"\"", "'()", "[]", "{}"] {
+        assert_eq!(eval(&format!("(if {} 1 2)", value)), "1", "{}", value);
+    }
+    assert_eq!(eval("(when 0 1 2)"), "2");
+    assert_eq!(eval("(when nil 1)"), "nil");
+    assert_eq!(eval("(unless false 1 2)"), "2");
+    assert_eq!(eval("(unless 0 1)"), "nil");
+}
+
+#[test]
+fn do_and_begin_sequence_their_forms() {
+    assert_eq!(eval("(do)"), "nil");
+    assert_eq!(eval("(do 1 2)"), "2");
+    assert_eq!(eval("(begin (def a 1) (+ a 1))"), "2");
+}
@@ -0,0 +1,167 @@
+use risp::lexer::{tokenize, TokenKind, Trivia, TriviaKind};
+use risp::span::Source;
+
+fn kinds(text: &str) -> Vec<TokenKind> {
+    let source = Source::new("test", text);
+    let tokens = tokenize(&source).unwrap();
+    tokens.into_iter().map(|token| token.kind).collect()
+}
+
+fn lex_error(text: &str) -> String {
+    let source = Source::new("test", text);
+    match tokenize(&source) {
+        Ok(tokens) => panic!("{} lexed as {:?}", text, tokens),
+        Err(err) => err.to_string(),
+    }
+}
+
+fn str_token(s: &str) -> TokenKind {
+    TokenKind::Str(s.to_string())
+}
+
+#[test]
+fn strings_keep_whitespace_and_delimiters() {
+    assert_eq!(
+        kinds(r#"(f "hello world" "(a) [b] {c} ; d")"#),
+        [
+            TokenKind::LParen,
+            TokenKind::Atom("f".to_string()),
+            str_token("hello world"),
+            str_token("(a) [b] {c} ; d"),
+            TokenKind::RParen,
+        ]
+    );
+    assert_eq!(kinds(r#""""#), [str_token("")]);
+    assert_eq!(kinds("\"two\nlines\""), [str_token("two\nlines")]);
+}
+
+#[test]
+fn string_escapes() {
+    assert_eq!(
+        kinds(r#""\n \t \r \0 \" \\""#),
+        [str_token("\n \t \r \0 \" \\")]
+    );
+    assert_eq!(
+        kinds(r#""\u{41}\u{e9}\u{1F600}""#),
+        [str_token("A\u{e9}\u{1F600}")]
+    );
+}
+
+#[test]
+fn malformed_strings_are_errors() {
+    assert!(lex_error(r#""no end"#).contains("unterminated string literal"));
+    assert!(lex_error(r#""ends in \"#).contains("unterminated string literal"));
+    assert!(lex_error(r#""\q""#).contains("unknown escape sequence `\\q`"));
+    assert!(lex_error(r#""\u41""#).contains("expected `{` after `\\u`"));
+    assert!(lex_error(r#""\u{41""#).contains("invalid `\\u{...}` escape"));
+    assert!(lex_error(r#""\u{}""#).contains("invalid unicode scalar value"));
+    assert!(lex_error(r#""\u{110000}""#).contains("invalid unicode scalar value"));
+    assert!(lex_error(r#""\u{d800}""#).contains("invalid unicode scalar value"));
+}
+
+#[test]
+fn errors_point_at_the_escape() {
+    let err = lex_error(r#"(f "ok \q")"#);
+    assert!(err.starts_with("test:1:8:"), "{}", err);
+}
+
+#[test]
+fn characters_run_to_the_next_delimiter() {
+    assert_eq!(
+        kinds(r"(#\a #\1 #\( #\) #\space #\é)"),
+        [
+            TokenKind::LParen,
+            TokenKind::Char('a'),
+            TokenKind::Char('1'),
+            TokenKind::Char('('),
+            TokenKind::Char(')'),
+            TokenKind::Char(' '),
+            TokenKind::Char('é'),
+            TokenKind::RParen,
+        ]
+    );
+    assert!(lex_error(r"(list #\12)").contains("unknown character name `#\\12`"));
+    assert!(lex_error(r"#\abc").contains("unknown character name `#\\abc`"));
+    assert!(lex_error(r"#\(x").contains("unknown character name"));
+}
+
+/// Each token with the text of its leading and trailing comments.
+fn trivia(text: &str) -> Vec<(TokenKind, Vec<String>, Vec<String>)> {
+    let source = Source::new("test", text);
+    let texts = |trivia: &[Trivia]| trivia.iter().map(|t| t.text.clone()).collect();
+    tokenize(&source)
+        .unwrap()
+        .into_iter()
+        .map(|token| {
+            (
+                token.kind.clone(),
+                texts(&token.leading),
+                texts(&token.trailing),
+            )
+        })
+        .collect()
+}
+
+fn atom(name: &str) -> TokenKind {
+    TokenKind::Atom(name.to_string())
+}
+
+#[test]
+fn comments_attach_to_the_nearest_token() {
+    let tokens = trivia(
+        "; about a\n\
+         ; more about a\n\
+         a ; after a\n\
+         #| before b |# b\n\
+         c ; at the end",
+    );
+    let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        tokens,
+        [
+            (
+                atom("a"),
+                strings(&["; about a", "; more about a"]),
+                strings(&["; after a"])
+            ),
+            (atom("b"), strings(&["#| before b |#"]), vec![]),
+            (atom("c"), vec![], strings(&["; at the end"])),
+        ]
+    );
+}
+
+#[test]
+fn comments_carry_their_kind_and_span() {
+    let source = Source::new("test", "x\n  #| a\n b |# y");
+    let tokens = tokenize(&source).unwrap();
+    let comment = &tokens[1].leading[0];
+    assert_eq!(comment.kind, TriviaKind::BlockComment);
+    assert_eq!(comment.text, "#| a\n b |#");
+    assert_eq!((comment.span.line, comment.span.col), (2, 3));
+}
+
+#[test]
+fn block_comments_nest() {
+    assert_eq!(
+        kinds("a #| outer #| inner |# still outer |# b"),
+        [atom("a"), atom("b")]
+    );
+    assert!(lex_error("a #| outer #| inner |# b").contains("unterminated block comment"));
+}
+
+#[test]
+fn discard_is_a_token_the_reader_acts_on() {
+    assert_eq!(
+        kinds("#_ (a) b"),
+        [
+            TokenKind::Discard,
+            TokenKind::LParen,
+            atom("a"),
+            TokenKind::RParen,
+            atom("b"),
+        ]
+    );
+    let interp = risp::Interpreter::new();
+    let value = interp.eval_str("[1 #_ 2 #_ #_ 3 4 5 ; done\n]").unwrap();
+    assert_eq!(value.to_string(), "[1 5]");
+}
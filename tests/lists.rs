@@ -0,0 +1,19 @@
+mod common;
+
+use common::eval;
+use risp::Interpreter;
+
+#[test]
+fn range_steps() {
+    assert_eq!(eval("(range 4)"), "(0 1 2 3)");
+    assert_eq!(eval("(range 5 0 -2)"), "(5 3 1)");
+    assert_eq!(eval("(range 0 1 0.25)"), "(0 0.25 0.5 0.75)");
+}
+
+#[test]
+fn range_rejects_steps_that_do_not_advance() {
+    let interp = Interpreter::new();
+    assert!(interp.eval_str("(range 0 1 0)").is_err());
+    let err = interp.eval_str("(range 1e17 1e18 1.0)").unwrap_err();
+    assert!(err.to_string().contains("does not advance"), "{}", err);
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use common::eval_in;
+use risp::Interpreter;
+
+fn with_macros() -> Interpreter {
+    let interp = Interpreter::new();
+    interp
+        .eval_str(
+            "(def (not x) (if x false true))
+             (defmacro my-unless (c & body) `(if ,c nil (do ,@body)))
+             (defmacro my-when (c & body) `(my-unless (not ,c) ,@body))",
+        )
+        .unwrap();
+    interp
+}
+
+#[test]
+fn macros_receive_unevaluated_arguments() {
+    let interp = with_macros();
+    assert_eq!(eval_in(&interp, "(my-unless false 1 2)"), "2");
+    assert_eq!(eval_in(&interp, "(my-when true 1 2)"), "2");
+    // The body is never evaluated, so the unbound symbol is not an error.
+    assert_eq!(eval_in(&interp, "(my-unless true undefined-thing)"), "nil");
+}
+
+#[test]
+fn macroexpand_1_expands_once_and_macroexpand_fully() {
+    let interp = with_macros();
+    assert_eq!(
+        eval_in(&interp, "(macroexpand-1 '(my-when x y z))"),
+        "(my-unless (not x) y z)"
+    );
+    assert_eq!(
+        eval_in(&interp, "(macroexpand '(my-when x y z))"),
+        "(if (not x) nil (do y z))"
+    );
+    assert_eq!(eval_in(&interp, "(macroexpand '(+ 1 2))"), "(+ 1 2)");
+}
+
+#[test]
+fn gensym_avoids_capturing_the_callers_names() {
+    let interp = Interpreter::new();
+    interp
+        .eval_str(
+            "(defmacro swap! (a b)
+               (let ((tmp (gensym)))
+                 `(let ((,tmp ,a)) (set! ,a ,b) (set! ,b ,tmp))))
+             (def tmp 1)
+             (def other 2)
+             (swap! tmp other)",
+        )
+        .unwrap();
+    assert_eq!(eval_in(&interp, "[tmp other]"), "[2 1]");
+    assert_eq!(eval_in(&interp, "(= (gensym) (gensym))"), "false");
+}
+
+#[test]
+fn macro_arity_is_checked() {
+    let interp = with_macros();
+    assert!(interp.eval_str("(my-unless)").is_err());
+}
+
+#[test]
+fn quasiquote_fills_in_collection_literals() {
+    let interp = Interpreter::new();
+    interp.eval_str("(def xs '(1 2))").unwrap();
+    assert_eq!(eval_in(&interp, "`[0 ,(+ 1 1) ,@xs]"), "[0 2 1 2]");
+    assert_eq!(eval_in(&interp, "(get `{:a ,(+ 1 1)} :a)"), "2");
+    assert_eq!(eval_in(&interp, "`#{,(+ 1 1)}"), "#{2}");
+    assert_eq!(eval_in(&interp, "(count `#{0 ,@xs})"), "3");
+    assert_eq!(eval_in(&interp, "(get `{,(+ 1 1) :two} 2)"), ":two");
+    // Nested quasiquotes leave their unquotes alone.
+    assert_eq!(
+        eval_in(&interp, "`{:a `{:b ,(+ 1 1)}}"),
+        "{:a (quasiquote {:b (unquote (+ 1 1))})}"
+    );
+    let err = interp.eval_str("`{,(+ 1 1) :a 2 :b}").unwrap_err();
+    assert!(err.to_string().contains("duplicate key '2'"), "{}", err);
+    let err = interp.eval_str("`#{2 ,(+ 1 1)}").unwrap_err();
+    assert!(err.to_string().contains("duplicate element '2'"), "{}", err);
+    assert!(interp.eval_str("`{:a ,@xs}").is_err());
+}
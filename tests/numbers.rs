@@ -0,0 +1,94 @@
+mod common;
+
+use common::{eval, eval_exp};
+use risp::number::Number;
+use risp::{Interpreter, RispExp};
+
+fn number(source: &str) -> Number {
+    match eval_exp(source) {
+        RispExp::Number(n) => n,
+        other => panic!("{} gave {}", source, other),
+    }
+}
+
+#[test]
+fn integers_promote_to_big_and_back() {
+    assert_eq!(eval("(+ 9223372036854775807 1)"), "9223372036854775808");
+    assert_eq!(eval("(- -9223372036854775808 1)"), "-9223372036854775809");
+    assert_eq!(eval("(* 4294967296 4294967296)"), "18446744073709551616");
+    assert!(matches!(
+        number("(+ 9223372036854775807 1)"),
+        Number::Big(_)
+    ));
+    assert!(matches!(
+        number("(- (+ 9223372036854775807 1) 1)"),
+        Number::Int(9223372036854775807)
+    ));
+}
+
+#[test]
+fn single_arguments_negate_and_invert() {
+    assert_eq!(eval("(- 5)"), "-5");
+    assert_eq!(eval("(- -9223372036854775808)"), "9223372036854775808");
+    assert_eq!(eval("(- 1/2)"), "-1/2");
+    assert_eq!(eval("(- 2.5)"), "-2.5");
+    assert_eq!(eval("(/ 2)"), "1/2");
+    assert_eq!(eval("(/ -2/3)"), "-3/2");
+    assert_eq!(eval("(/ 4.0)"), "0.25");
+    assert!(Interpreter::new().eval_str("(/ 0)").is_err());
+}
+
+#[test]
+fn division_is_exact() {
+    assert_eq!(eval("(/ 1 3)"), "1/3");
+    assert_eq!(eval("(/ -6 4)"), "-3/2");
+    assert!(matches!(number("(/ 6 3)"), Number::Int(2)));
+    assert!(matches!(number("(+ 1/3 2/3)"), Number::Int(1)));
+    assert_eq!(eval("(= 1/10 (+ 1/20 1/20))"), "true");
+}
+
+#[test]
+fn floats_are_contagious() {
+    assert_eq!(eval("(+ 1 0.5)"), "1.5");
+    assert_eq!(eval("(+ 1/2 0.5)"), "1.0");
+    assert_eq!(eval("(* 2 1.5)"), "3.0");
+    assert_eq!(eval("(exact->inexact 1/4)"), "0.25");
+    assert_eq!(eval("(= 1 1.0)"), "true");
+    assert_eq!(eval("(< 1/3 0.34 1/2 1)"), "true");
+    assert_eq!(
+        eval("(> 9223372036854775808 9223372036854775807.0)"),
+        "false"
+    );
+}
+
+#[test]
+fn rounding_keeps_exactness() {
+    assert_eq!(eval("(floor 7/2)"), "3");
+    assert_eq!(eval("(floor -7/2)"), "-4");
+    assert_eq!(eval("(floor -3.5)"), "-4.0");
+    // Halves round to even.
+    assert_eq!(eval("(round 5/2)"), "2");
+    assert_eq!(eval("(round 7/2)"), "4");
+    assert_eq!(eval("(round 2.5)"), "2.0");
+}
+
+#[test]
+fn integer_division() {
+    assert_eq!(eval("(quotient -7 2)"), "-3");
+    assert_eq!(eval("(remainder -7 2)"), "-1");
+    assert_eq!(eval("(modulo -7 2)"), "1");
+    assert_eq!(eval("(modulo 7 -2)"), "-1");
+    assert_eq!(
+        eval("(quotient 100000000000000000000 3)"),
+        "33333333333333333333"
+    );
+    assert!(Interpreter::new().eval_str("(quotient 7.5 2)").is_err());
+}
+
+#[test]
+fn indices_must_be_exact_integers() {
+    assert_eq!(eval("(nth 1 '(a b c))"), "b");
+    let interp = Interpreter::new();
+    assert!(interp.eval_str("(nth 1.5 '(a b c))").is_err());
+    assert!(interp.eval_str("(nth 1.0 '(a b c))").is_err());
+}
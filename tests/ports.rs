@@ -0,0 +1,71 @@
+mod common;
+
+use common::eval_in;
+use risp::port::{current_output, with_output, Port};
+use risp::Interpreter;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+#[test]
+fn with_output_to_string_captures_every_writer() {
+    let interp = Interpreter::new();
+    assert_eq!(
+        eval_in(
+            &interp,
+            r#"(with-output-to-string
+                 (fn ()
+                   (display "a")
+                   (write "b")
+                   (newline)
+                   (print 1 "c")
+                   (println #\d [2 "e"])
+                   (pprint '(f "g"))))"#
+        ),
+        r#""a\"b\"\n1 cd [2 e]\n(f \"g\")\n""#
+    );
+}
+
+#[test]
+fn read_line_splits_on_any_line_ending() {
+    let interp = Interpreter::new();
+    interp
+        .eval_str(r#"(def in (open-input-string "one\r\ntwo\n\nthree"))"#)
+        .unwrap();
+    let lines: Vec<String> = (0..5).map(|_| eval_in(&interp, "(read-line in)")).collect();
+    assert_eq!(
+        lines,
+        [r#""one""#, r#""two""#, r#""""#, r#""three""#, "nil"]
+    );
+}
+
+#[test]
+fn output_port_is_restored_after_a_throw() {
+    let interp = Interpreter::new();
+    let before = eval_in(&interp, "(current-output-port)");
+    assert_eq!(
+        eval_in(
+            &interp,
+            r#"(try (with-output-to-string (fn () (display "lost") (throw :oops)))
+                    (catch e e))"#
+        ),
+        ":oops"
+    );
+    assert_eq!(eval_in(&interp, "(current-output-port)"), before);
+    assert_eq!(
+        eval_in(
+            &interp,
+            r#"(with-output-to-string (fn () (display "kept")))"#
+        ),
+        r#""kept""#
+    );
+}
+
+#[test]
+fn output_port_is_restored_after_a_panic() {
+    let before = current_output();
+    let port = Port::output_string();
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        with_output(port.clone(), || panic!("inside with_output"))
+    }));
+    assert!(res.is_err());
+    assert!(current_output() == before);
+}
@@ -0,0 +1,69 @@
+use risp::Interpreter;
+
+fn pprint(interp: &Interpreter, form: &str, width: usize) -> String {
+    let source = format!(
+        "(with-output-to-string (fn () (pprint '{} {})))",
+        form, width
+    );
+    match interp.eval_str(&source).unwrap() {
+        risp::RispExp::Literal(s) => s,
+        other => panic!("pprint wrote {}", other),
+    }
+}
+
+#[test]
+fn call_arguments_that_are_atoms_fill_the_line() {
+    let interp = Interpreter::new();
+    assert_eq!(
+        pprint(
+            &interp,
+            "(vector 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20)",
+            30
+        ),
+        "(vector 1 2 3 4 5 6 7 8 9 10\n  11 12 13 14 15 16 17 18 19\n  20)\n"
+    );
+}
+
+#[test]
+fn call_arguments_that_are_forms_get_a_line_each() {
+    let interp = Interpreter::new();
+    assert_eq!(
+        pprint(
+            &interp,
+            "(define (f x) (if (> x 10) (list x x) (vector x x x)))",
+            30
+        ),
+        "(define (f x)\n  (if (> x 10)\n    (list x x)\n    (vector x x x)))\n"
+    );
+}
+
+#[test]
+fn print_indent_sets_the_indentation() {
+    let interp = Interpreter::new();
+    interp.eval_str("(def *print-indent* 4)").unwrap();
+    assert_eq!(
+        pprint(&interp, "(define (f x) (list x x x x x x x x))", 20),
+        "(define (f x)\n    (list x x x x x\n        x x x))\n"
+    );
+}
+
+#[test]
+fn short_values_stay_on_one_line() {
+    let interp = Interpreter::new();
+    assert_eq!(
+        pprint(&interp, "(define (f x) x)", 80),
+        "(define (f x) x)\n"
+    );
+    assert_eq!(pprint(&interp, "{:a [1 2]}", 80), "{:a [1 2]}\n");
+}
+
+#[test]
+fn print_width_sets_the_width() {
+    let interp = Interpreter::new();
+    interp.eval_str("(def *print-width* 20)").unwrap();
+    let vector = "[1 2 3 4 5 6 7 8 9 10 11 12]";
+    let shown = interp.pretty_print(&interp.eval_str(vector).unwrap());
+    assert_eq!(shown, "[1 2 3 4 5 6 7 8 9\n 10 11 12]");
+    // The `pprint` argument overrides the variable.
+    assert_eq!(pprint(&interp, vector, 80), format!("{}\n", vector));
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::eval_in;
+use risp::Interpreter;
+
+/// Checks `(equal? x (read-string (pr-str x)))` for the value of `form`.
+fn assert_round_trips(form: &str) {
+    let interp = Interpreter::new();
+    interp.eval_str(&format!("(def x {})", form)).unwrap();
+    assert_eq!(
+        eval_in(&interp, "(equal? x (read-string (pr-str x)))"),
+        "true",
+        "{} printed as {}",
+        form,
+        eval_in(&interp, "(pr-str x)")
+    );
+}
+
+#[test]
+fn values_round_trip_through_pr_str() {
+    let forms = [
+        // Strings with escapes.
+        r#""plain""#,
+        r#""quote \" backslash \\ tab \t newline \n return \r nul \0""#,
+        r#""bell \u{7} and é""#,
+        // Characters.
+        r"#\a",
+        r"#\space",
+        r"#\newline",
+        r"#\tab",
+        r"#\é",
+        // Numbers.
+        "42",
+        "-7",
+        "1.5",
+        "0.1",
+        "-2.0",
+        "1e300",
+        "(/ 1 0.0)",
+        "(/ -1 0.0)",
+        "(- (/ 1 0.0) (/ 1 0.0))",
+        "3/4",
+        "-22/7",
+        "123456789012345678901234567890",
+        "-98765432109876543210",
+        // Atoms.
+        ":keyword",
+        "'symbol",
+        "nil",
+        "true",
+        // Nested collections.
+        "'(1 (2 \"three\" [4 #\\5]) {:six #{7 8}})",
+        "'[[] () {} #{}]",
+        "{:a {:b {:c [1 2 3]}} \"key\" #{:x :y}}",
+        // Patterns.
+        r#"#"\d+\.\d*""#,
+        r#"#"say \"hi\"""#,
+        r#"(re-pattern "a\\\\\"b")"#,
+    ];
+    for form in forms {
+        assert_round_trips(form);
+    }
+}
+
+#[test]
+fn read_string_reads_exactly_one_datum() {
+    let interp = Interpreter::new();
+    assert_eq!(
+        eval_in(&interp, r#"(read-string "  (1 2) ; a comment")"#),
+        "(1 2)"
+    );
+    assert_eq!(
+        eval_in(&interp, r##"(read-string "#_skipped [3]")"##),
+        "[3]"
+    );
+    let err = interp.eval_str(r#"(read-string "1 2")"#).unwrap_err();
+    assert!(err.to_string().contains("unexpected data"), "{}", err);
+    assert!(interp.eval_str(r#"(read-string "1 #_2 3")"#).is_err());
+    assert!(interp
+        .eval_str(r#"(read-string "; only a comment")"#)
+        .is_err());
+}
+
+#[test]
+fn keywords_must_have_a_name() {
+    let interp = Interpreter::new();
+    assert_eq!(eval_in(&interp, r#"(keyword "a")"#), ":a");
+    assert!(interp.eval_str(r#"(keyword "")"#).is_err());
+}
+
+#[test]
+fn display_and_write_forms() {
+    let interp = Interpreter::new();
+    assert_eq!(
+        eval_in(&interp, r#"(print-str "a" #\b :c [1 "d"])"#),
+        r#""a b :c [1 d]""#
+    );
+    assert_eq!(
+        eval_in(&interp, r#"(pr-str "a" #\b :c [1 "d"])"#),
+        r#""\"a\" #\\b :c [1 \"d\"]""#
+    );
+}
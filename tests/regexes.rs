@@ -0,0 +1,28 @@
+mod common;
+
+use common::eval;
+
+#[test]
+fn patterns_print_as_literals_that_read_back() {
+    // A quote, an escaped quote and an escaped backslash before a quote.
+    for source in [r#""a\"b""#, r#""a\\\"b""#, r#""a\\\\\"b""#, r#""\\d+""#] {
+        let pattern = format!("(re-pattern {})", source);
+        assert_eq!(
+            eval(&format!("(equal? {0} (read-string (pr-str {0})))", pattern)),
+            "true",
+            "{}",
+            pattern
+        );
+    }
+    assert_eq!(eval(r#"(re-pattern "a\\\"b")"#), r#"#"a\"b""#);
+    assert_eq!(eval(r#"(re-pattern "a\\\\\"b")"#), r#"#"a\\\"b""#);
+}
+
+#[test]
+fn escaped_quotes_match_quotes() {
+    assert_eq!(eval(r#"(re-find #"a\"b" "xa\"b")"#), r#""a\"b""#);
+    assert_eq!(
+        eval(r#"(re-find (re-pattern "a\\\"b") "xa\"b")"#),
+        r#""a\"b""#
+    );
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use common::eval_exp;
+use risp::{from_value, to_value, Interpreter};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Color {
+    Named(String),
+    Rgb(u8, u8, u8),
+    Default,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Server {
+    host: String,
+    port: u16,
+    color: Color,
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Config {
+    name: String,
+    servers: Vec<Server>,
+}
+
+fn config() -> Config {
+    Config {
+        name: "prod".to_string(),
+        servers: vec![
+            Server {
+                host: "a".to_string(),
+                port: 80,
+                color: Color::Rgb(1, 2, 3),
+                tags: Some(vec!["web".to_string()]),
+            },
+            Server {
+                host: "b".to_string(),
+                port: 443,
+                color: Color::Default,
+                tags: None,
+            },
+        ],
+    }
+}
+
+type Servers = HashMap<String, Vec<HashMap<String, u16>>>;
+
+fn error_for<T: serde::de::DeserializeOwned + std::fmt::Debug>(source: &str) -> String {
+    match from_value::<T>(&eval_exp(source)) {
+        Ok(value) => panic!("{} deserialized as {:?}", source, value),
+        Err(err) => err.to_string(),
+    }
+}
+
+#[test]
+fn structs_and_enums_round_trip() {
+    let value = to_value(&config()).unwrap();
+    assert_eq!(from_value::<Config>(&value).unwrap(), config());
+    let interp = Interpreter::new();
+    interp.define_global("config", value);
+    assert_eq!(
+        interp
+            .eval_str("(get (nth 0 (get config :servers)) :color)")
+            .unwrap()
+            .to_string(),
+        "{:Rgb [1 2 3]}"
+    );
+    assert_eq!(
+        interp
+            .eval_str("(get (nth 1 (get config :servers)) :color)")
+            .unwrap()
+            .to_string(),
+        ":Default"
+    );
+    let script = r#"{:name "dev"
+                     :servers [{:host "c" :port 8080 :color {:Named "red"} :tags nil}]}"#;
+    let dev: Config = from_value(&eval_exp(script)).unwrap();
+    assert_eq!(dev.servers[0].color, Color::Named("red".to_string()));
+}
+
+#[test]
+fn struct_errors_name_the_field() {
+    let script = r#"{:name "dev"
+                     :servers [{:host "c" :port 80 :color {:Rgb [1 2 300]} :tags nil}]}"#;
+    let err = error_for::<Config>(script);
+    assert!(err.starts_with("at `servers[0].color.Rgb[2]`: "), "{}", err);
+    let err = error_for::<Config>(r#"{:name "dev" :servers [{:host "c" :color :Default}]}"#);
+    assert!(err.starts_with("at `servers[0]`: "), "{}", err);
+    assert!(err.contains("missing field `port`"), "{}", err);
+    let err = error_for::<Config>(r#"{:servers []}"#);
+    assert!(err.contains("missing field `name`"), "{}", err);
+    let err = error_for::<Color>(":Blue");
+    assert!(err.contains("unknown variant `Blue`"), "{}", err);
+}
+
+#[test]
+fn script_values_deserialize() {
+    let servers: Servers =
+        from_value(&eval_exp("{:servers [{:port 80} {:port 443 :weight 2}]}")).unwrap();
+    assert_eq!(servers["servers"][1]["port"], 443);
+    let pair: (i64, Option<String>) = from_value(&eval_exp("[1 nil]")).unwrap();
+    assert_eq!(pair, (1, None));
+}
+
+#[test]
+fn errors_name_the_path_to_the_mismatch() {
+    let err = error_for::<Servers>("{:servers [{:port 80} {:port \"http\"}]}");
+    assert!(err.starts_with("at `servers[1].port`: "), "{}", err);
+    let err = error_for::<Servers>("{:servers [{:port 80} {:port 70000}]}");
+    assert!(err.starts_with("at `servers[1].port`: "), "{}", err);
+    let err = error_for::<HashMap<String, Vec<i64>>>("{\"a b\" [1 2 :three]}");
+    assert!(err.starts_with("at `a b[2]`: "), "{}", err);
+    let err = error_for::<Vec<Vec<bool>>>("[[true] [false 1]]");
+    assert!(err.starts_with("at `[1][1]`: "), "{}", err);
+}
+
+#[test]
+fn caught_errors_carry_the_path() {
+    let interp = Interpreter::new();
+    let err = match from_value::<Servers>(&eval_exp("{:servers [{:port :x}]}")) {
+        Ok(value) => panic!("deserialized as {:?}", value),
+        Err(err) => err,
+    };
+    interp.define_global("err", err.into_value());
+    assert_eq!(
+        interp
+            .eval_str("(get (error-data err) :path)")
+            .unwrap()
+            .to_string(),
+        "\"servers[0].port\""
+    );
+}
+
+#[test]
+fn rust_values_serialize() {
+    let mut map = BTreeMap::new();
+    map.insert("b", vec![Some(1.5), None]);
+    map.insert("a", vec![]);
+    let interp = Interpreter::new();
+    interp.define_global("x", to_value(&map).unwrap());
+    assert_eq!(
+        interp.eval_str("(get x \"b\")").unwrap().to_string(),
+        "[1.5 nil]"
+    );
+    assert_eq!(interp.eval_str("(count x)").unwrap().to_string(), "2");
+    assert_eq!(
+        to_value(&(1u64 << 63)).unwrap().to_string(),
+        "9223372036854775808"
+    );
+}
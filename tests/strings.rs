@@ -0,0 +1,72 @@
+mod common;
+
+use common::eval;
+use risp::Interpreter;
+
+fn error(source: &str) -> String {
+    Interpreter::new().eval_str(source).unwrap_err().to_string()
+}
+
+#[test]
+fn strings_are_indexed_by_character() {
+    assert_eq!(eval(r#"(string-length "héllo wörld")"#), "11");
+    assert_eq!(eval(r#"(substring "héllo wörld" 1 4)"#), r#""éll""#);
+    assert_eq!(eval(r#"(substring "héllo wörld" 7)"#), r#""örld""#);
+    assert_eq!(eval(r#"(substring "日本語" 1 2)"#), r#""本""#);
+    assert_eq!(eval(r#"(substring "héllo" 5)"#), r#""""#);
+    assert!(error(r#"(substring "héllo" 2 9)"#).contains("out of bounds"));
+    assert!(error(r#"(substring "héllo" 3 2)"#).contains("out of bounds"));
+}
+
+#[test]
+fn string_split_on_whitespace_or_a_separator() {
+    assert_eq!(eval(r#"(string-split "  a b\t\nc  ")"#), r#"("a" "b" "c")"#);
+    assert_eq!(
+        eval(r#"(string-split "a,b,,c" ",")"#),
+        r#"("a" "b" "" "c")"#
+    );
+    assert_eq!(eval(r#"(string-split "a->b" "->")"#), r#"("a" "b")"#);
+    assert_eq!(eval(r#"(string-split "" ",")"#), r#"("")"#);
+}
+
+#[test]
+fn format_directives() {
+    assert_eq!(
+        eval(r#"(format "~a and ~s~%~~" "x" "y")"#),
+        r#""x and \"y\"\n~""#
+    );
+    assert_eq!(eval(r#"(format "~a ~s" #\c #\c)"#), r#""c #\\c""#);
+    assert_eq!(eval(r#"(format "~a" [1 "two"])"#), r#""[1 two]""#);
+    assert_eq!(eval(r#"(format "plain")"#), r#""plain""#);
+}
+
+#[test]
+fn format_checks_its_arguments() {
+    assert!(error(r#"(format "~a ~a" 1)"#).contains("too few arguments"));
+    assert!(error(r#"(format "~a" 1 2)"#).contains("too many arguments"));
+    assert!(error(r#"(format "~q" 1)"#).contains("unknown directive '~q'"));
+}
+
+#[test]
+fn string_to_number_returns_nil_for_non_numbers() {
+    assert_eq!(eval(r#"(string->number "42")"#), "42");
+    assert_eq!(eval(r#"(string->number "-1/2")"#), "-1/2");
+    assert_eq!(eval(r#"(string->number "2.5")"#), "2.5");
+    assert_eq!(eval(r#"(string->number "abc")"#), "nil");
+    assert_eq!(eval(r#"(string->number "1 2")"#), "nil");
+    assert_eq!(eval(r#"(string->number "")"#), "nil");
+    assert_eq!(eval(r#"(number->string 1/3)"#), r#""1/3""#);
+}
+
+#[test]
+fn the_rest_of_the_library() {
+    assert_eq!(eval(r#"(str "a" 1 :b nil #\c)"#), r#""a1:bc""#);
+    assert_eq!(eval(r#"(string-join ["a" "b" "c"] ", ")"#), r#""a, b, c""#);
+    assert_eq!(eval(r#"(string-trim "  x y \n")"#), r#""x y""#);
+    assert_eq!(eval(r#"(string-upcase "straße")"#), r#""STRASSE""#);
+    assert_eq!(eval(r#"(string-downcase "ÀB")"#), r#""àb""#);
+    assert_eq!(eval(r#"(string-contains? "héllo" "él")"#), "true");
+    assert_eq!(eval(r#"(string-replace "a-b-c" "-" "+")"#), r#""a+b+c""#);
+    assert_eq!(eval(r#"(symbol->string 'abc)"#), r#""abc""#);
+    assert_eq!(eval(r#"(string->symbol "abc")"#), "abc");
+}